@@ -1,24 +1,67 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use crate::data::{Schedule, Term};
-use crate::requirements::{parse, EvalExpression, Expression};
+use crate::requirements::{
+    parse, try_parse, EvalExpression, Evaluation, ExplainExpression, Expression, Primary,
+    RequisiteName, RequisiteType,
+};
 
-use serde::Deserialize;
+use itertools::Itertools;
+use serde::{de, Deserialize, Deserializer};
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Class {
     subject: String,
     number: u16,
-    pub credits: u8,
+    #[serde(rename = "credits", deserialize_with = "credit_range")]
+    credit_range: (u8, u8),
     pub required: bool,
     groups: String,
-    pub semesters: String,
+    semesters: String,
     requisites: String,
+    #[serde(default)]
+    bundle: String,
+    #[serde(default)]
+    repeats: Option<u8>,
+    /// Credits picked for a variable-credit class once it's placed in a semester.
+    #[serde(skip)]
+    chosen_credits: Option<u8>,
     #[serde(skip)]
     parsed_reqs: Option<Expression>,
+    #[serde(skip)]
+    linked: Vec<RequisiteName>,
+}
+
+/// Reads either a number of credits (`3`) or a range for variable-credit classes (`1-4`).
+fn credit_range<'de, D: Deserializer<'de>>(deserializer: D) -> Result<(u8, u8), D::Error> {
+    let text = String::deserialize(deserializer)?;
+    let parse = |credits: &str| {
+        credits
+            .trim()
+            .parse::<u8>()
+            .map_err(|_| de::Error::custom(format!("invalid credits \"{text}\"")))
+    };
+    let (min, max) = match text.split_once('-') {
+        Some((min, max)) => (parse(min)?, parse(max)?),
+        None => (parse(&text)?, parse(&text)?),
+    };
+    if min > max {
+        return Err(de::Error::custom(format!("invalid credits \"{text}\"")));
+    }
+    Ok((min, max))
+}
+
+impl PartialEq for Class {
+    fn eq(&self, other: &Self) -> bool {
+        self.subject == other.subject && self.number == other.number
+    }
 }
 
 impl PartialOrd for Class {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.name().cmp(&other.name()))
+        Some(self.cmp(other))
     }
 }
 
@@ -35,12 +78,112 @@ impl Class {
         format!("{} {}", self.subject, self.number)
     }
 
+    /// Credits the class is taken for. Variable-credit classes count for their minimum until
+    /// they're placed in a semester.
+    pub fn credits(&self) -> u8 {
+        self.chosen_credits.unwrap_or(self.credit_range.0)
+    }
+
+    pub fn credit_range(&self) -> (u8, u8) {
+        self.credit_range
+    }
+
+    pub fn is_variable(&self) -> bool {
+        self.credit_range.0 != self.credit_range.1
+    }
+
+    /// A copy of the class taken for `credits` credits.
+    pub fn with_credits(&self, credits: u8) -> Class {
+        Class {
+            chosen_credits: Some(credits),
+            ..self.clone()
+        }
+    }
+
+    /// How many times the class can be taken.
+    pub fn repeats(&self) -> u8 {
+        self.repeats.unwrap_or(1)
+    }
+
+    /// Whether the class is in `taken` and can't be taken again, so it's left out of planning.
+    pub fn used_up(&self, taken: &[String]) -> bool {
+        self.repeats() <= 1 && taken.contains(&self.name())
+    }
+
+    /// Parses requisites ahead of time, so they aren't parsed again every time they're checked.
+    /// Requisites which can't be parsed are left to fail when they're checked.
+    pub fn cache_requisites(&mut self) {
+        self.parsed_reqs = self.parsed_requisites().ok();
+        self.linked = match &self.parsed_reqs {
+            Some(expression) => expression
+                .to_cnf()
+                .0
+                 .0
+                .into_iter()
+                .filter_map(|or| match or.0.as_slice() {
+                    [Primary::Req(req)] if req.typ == RequisiteType::Concurrent => {
+                        Some(req.name.clone())
+                    }
+                    _ => None,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+    }
+
+    fn expression(&self) -> Cow<'_, Expression> {
+        match &self.parsed_reqs {
+            Some(expression) => Cow::Borrowed(expression),
+            None => Cow::Owned(parse(&self.requisites)),
+        }
+    }
+
     pub fn requisites_met(&self, schedule: &Schedule) -> bool {
-        parse(&self.requisites).eval(schedule)
+        self.expression().eval(schedule)
+    }
+
+    /// Classes which must be taken in the same term as this one, no matter how the requisites
+    /// are met. Only available once requisites are cached.
+    pub fn linked(&self) -> &[RequisiteName] {
+        &self.linked
+    }
+
+    pub fn explain_requisites(&self, schedule: &Schedule) -> Evaluation {
+        self.expression().normalize().explain(schedule)
+    }
+
+    /// Names of the requisites keeping this class from being taken in the last semester of
+    /// `schedule`.
+    pub fn unmet_requisites(&self, schedule: &Schedule) -> Vec<String> {
+        self.expression()
+            .unmet(schedule)
+            .into_iter()
+            .map(|req| req.name.clone())
+            .unique()
+            .collect()
+    }
+
+    pub fn parsed_requisites(&self) -> Result<Expression, String> {
+        try_parse(&self.requisites)
     }
 
     pub fn offered(&self, term: &Term) -> bool {
-        self.semesters.split("|").any(|sem| term.matches(sem))
+        self.semesters().iter().any(|sem| term.matches(sem))
+    }
+
+    pub fn semesters(&self) -> Vec<&str> {
+        if self.semesters.is_empty() {
+            return Vec::new();
+        }
+        self.semesters.split("|").collect()
+    }
+
+    /// Classes sharing a bundle are always scheduled together, as if they were one class.
+    pub fn bundle(&self) -> Option<&str> {
+        if self.bundle.is_empty() {
+            return None;
+        }
+        Some(&self.bundle)
     }
 
     pub fn groups(&self) -> Vec<&str> {
@@ -50,3 +193,40 @@ impl Class {
         self.groups.split("|").collect()
     }
 }
+
+/// The taken classes which are in the catalog, so their credits and groups are known. Taken
+/// classes which aren't have no credits to count, so they're left to `taken_credits` in the
+/// config.
+pub fn taken_classes(classes: &[Arc<Class>], taken: &[String]) -> Vec<Arc<Class>> {
+    taken
+        .iter()
+        .filter_map(|name| classes.iter().find(|class| class.name() == *name))
+        .cloned()
+        .collect()
+}
+
+/// Credits for the taken classes which are in the catalog.
+pub fn taken_credits(classes: &[Arc<Class>], taken: &[String]) -> u16 {
+    taken_classes(classes, taken)
+        .iter()
+        .map(|class| class.credits() as u16)
+        .sum()
+}
+
+/// Groups classes, by index, into the units they're picked as: the classes in each bundle
+/// together, in the order the bundle first appears, and every other class on its own.
+pub fn units(classes: &[Arc<Class>]) -> Vec<Vec<usize>> {
+    let mut units: Vec<Vec<usize>> = Vec::new();
+    let mut bundles: HashMap<&str, usize> = HashMap::new();
+    for (index, class) in classes.iter().enumerate() {
+        match class.bundle() {
+            Some(bundle) if bundles.contains_key(bundle) => units[bundles[bundle]].push(index),
+            Some(bundle) => {
+                bundles.insert(bundle, units.len());
+                units.push(vec![index]);
+            }
+            None => units.push(vec![index]),
+        }
+    }
+    units
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use itertools::Itertools;
+use yansi::Paint;
+
+use crate::class::Class;
+use crate::config::Config;
+use crate::data::{Schedule, Term};
+
+/// Schedules which take their required classes in the same terms, and only differ in which
+/// electives they take alongside them.
+pub struct Cluster {
+    terms: Vec<TermSummary>,
+    schedules: usize,
+    complete: usize,
+}
+
+struct TermSummary {
+    term: Term,
+    required: Vec<String>,
+    /// Each different set of electives taken in the term, in the order they were first seen.
+    electives: Vec<Vec<String>>,
+    credits: (u16, u16),
+}
+
+/// Groups schedules by where their required classes are placed, keeping the order the schedules
+/// came in.
+pub fn collapse(scheds: &[Schedule], config: &Config) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let mut index: HashMap<Vec<(Term, Vec<String>)>, usize> = HashMap::new();
+
+    let names = |classes: Vec<&Arc<Class>>| -> Vec<String> {
+        classes
+            .into_iter()
+            .map(|class| class.name())
+            .sorted()
+            .collect()
+    };
+
+    for sched in scheds {
+        let placements: Vec<(Term, Vec<String>, Vec<String>, u16)> = sched
+            .semesters()
+            .iter()
+            .map(|sem| {
+                let (required, electives): (Vec<&Arc<Class>>, Vec<&Arc<Class>>) =
+                    sem.0.iter().partition(|class| class.required);
+                (sem.1, names(required), names(electives), sem.credits())
+            })
+            .collect();
+        let key: Vec<(Term, Vec<String>)> = placements
+            .iter()
+            .map(|(term, required, ..)| (*term, required.clone()))
+            .collect();
+
+        let cluster = *index.entry(key).or_insert_with(|| {
+            clusters.push(Cluster {
+                terms: placements
+                    .iter()
+                    .map(|(term, required, _, credits)| TermSummary {
+                        term: *term,
+                        required: required.clone(),
+                        electives: Vec::new(),
+                        credits: (*credits, *credits),
+                    })
+                    .collect(),
+                schedules: 0,
+                complete: 0,
+            });
+            clusters.len() - 1
+        });
+
+        let cluster = &mut clusters[cluster];
+        cluster.schedules += 1;
+        if sched.is_complete(config).is_ok() {
+            cluster.complete += 1;
+        }
+        for (summary, (_, _, electives, credits)) in cluster.terms.iter_mut().zip(placements) {
+            if !summary.electives.contains(&electives) {
+                summary.electives.push(electives);
+            }
+            summary.credits = (
+                summary.credits.0.min(credits),
+                summary.credits.1.max(credits),
+            );
+        }
+    }
+    clusters
+}
+
+impl Display for TermSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}{} ",
+            self.term.season.bold().blue(),
+            self.term.year.bold().blue(),
+            ":".bold().blue()
+        )?;
+        let mut parts: Vec<String> = Vec::new();
+        if !self.required.is_empty() {
+            parts.push(self.required.join(", ").green().to_string());
+        }
+        match self.electives.as_slice() {
+            [only] if only.is_empty() => {}
+            [only] => parts.push(only.join(", ").green().to_string()),
+            alternatives => {
+                let options: Vec<String> = alternatives
+                    .iter()
+                    .map(|electives| match electives.is_empty() {
+                        true => "nothing".to_owned(),
+                        false => electives.join(" + "),
+                    })
+                    .collect();
+                parts.push(
+                    format!("one of: {}", options.join(" | "))
+                        .cyan()
+                        .to_string(),
+                );
+            }
+        }
+        write!(f, "{} ", parts.join(", "))?;
+        match self.credits {
+            (min, max) if min == max => write!(f, "{}", format!("({min} credits)").bright_black()),
+            (min, max) => write!(f, "{}", format!("({min}-{max} credits)").bright_black()),
+        }
+    }
+}
+
+impl Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for term in self.terms.iter() {
+            writeln!(f, "{term}")?;
+        }
+        let count = format!("{}/{} schedules", self.complete, self.schedules);
+        let count = match self.complete {
+            0 => count.red(),
+            complete if complete == self.schedules => count.green(),
+            _ => count.yellow(),
+        };
+        write!(f, "{} {}", "Complete:".yellow().bold(), count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Semester;
+    use crate::testing::{classes, config, root, taken};
+
+    #[test]
+    fn clusters() {
+        let classes = classes(
+            "ABC,100,3,true,,Fall|Spring,
+             ABC,101,3,true,,Fall|Spring,
+             XYZ,100,1,false,elective,Fall|Spring,
+             XYZ,101,2,false,elective,Fall|Spring,",
+        );
+        let config = config(3, 6, 2, "groups = { elective = 2 }");
+        let schedule = |terms: &[&[&str]]| {
+            let mut sched = root(&classes, &taken(&[]), &config);
+            for names in terms {
+                let picked = classes
+                    .iter()
+                    .filter(|class| names.contains(&class.name().as_str()))
+                    .cloned()
+                    .collect();
+                let term = sched.next_term();
+                sched = sched.with_semester(Arc::new(Semester::new(picked, term)));
+            }
+            sched
+        };
+        let scheds = [
+            schedule(&[&["ABC 100", "XYZ 100"], &["ABC 101"]]),
+            schedule(&[&["ABC 101"], &["ABC 100", "XYZ 101"]]),
+            schedule(&[&["ABC 100", "XYZ 101"], &["ABC 101"]]),
+            schedule(&[&["ABC 100"], &["XYZ 100"]]),
+            schedule(&[&["ABC 100"], &["ABC 101"]]),
+            schedule(&[&["ABC 100"], &["XYZ 101"]]),
+        ];
+        let clusters = collapse(&scheds, &config);
+
+        // grouped by where the required classes are, in the order they were first seen
+        let required: Vec<Vec<Vec<String>>> = clusters
+            .iter()
+            .map(|cluster| {
+                cluster
+                    .terms
+                    .iter()
+                    .map(|term| term.required.clone())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            required,
+            [
+                vec![vec!["ABC 100"], vec!["ABC 101"]],
+                vec![vec!["ABC 101"], vec!["ABC 100"]],
+                vec![vec!["ABC 100"], vec![]],
+            ]
+        );
+
+        // the electives taken alongside them are merged into alternatives
+        let first = &clusters[0];
+        assert_eq!(
+            first.terms[0].electives,
+            [vec!["XYZ 100"], vec!["XYZ 101"], vec![]]
+        );
+        assert_eq!(first.terms[1].electives, [Vec::<String>::new()]);
+        assert_eq!(first.terms[0].credits, (3, 5));
+        assert_eq!(first.terms[1].credits, (3, 3));
+        let shown = first.to_string();
+        assert!(
+            shown.contains("one of: XYZ 100 | XYZ 101 | nothing"),
+            "{shown}"
+        );
+        assert!(shown.contains("(3-5 credits)"), "{shown}");
+
+        // only the schedule taking two elective credits is complete
+        let counts: Vec<(usize, usize)> = clusters
+            .iter()
+            .map(|cluster| (cluster.complete, cluster.schedules))
+            .collect();
+        assert_eq!(counts, [(1, 3), (1, 1), (0, 2)]);
+    }
+}
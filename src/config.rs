@@ -1,13 +1,21 @@
 use std::collections::BTreeMap;
+use std::fmt;
 
-use serde::Deserialize;
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{self, MapAccess, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer};
 
 use crate::data::Term;
+use crate::degree::Degree;
 
 const fn true_fn() -> bool {
     true // thank you serde very cool
 }
 
+const fn sample_distance_fn() -> f64 {
+    0.25
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub min_credits: u8,
@@ -15,7 +23,175 @@ pub struct Config {
     pub semesters: u8,
     pub starting_term: Term,
     #[serde(default)]
-    pub groups: BTreeMap<String, u8>,
+    pub groups: BTreeMap<String, Group>,
+    /// Sets of groups which a class can only count towards one of.
+    #[serde(default)]
+    pub exclusive: Vec<Vec<String>>,
+    /// Whether to show schedules which aren't complete. Turning it off also lets schedules be
+    /// cut short as soon as they miss the last chance for a required class.
     #[serde(default = "true_fn")]
     pub show_incomplete: bool,
+    /// A plan saved with `save` to continue from. Its terms are kept, and `semesters` more are
+    /// planned after them.
+    pub plan: Option<String>,
+    /// How many of the plan's terms to keep, defaulting to all of them.
+    pub locked_terms: Option<u8>,
+    /// Whether to group schedules which place required classes the same way, listing the
+    /// electives they take instead of every schedule.
+    #[serde(default)]
+    pub collapse_electives: bool,
+    /// Whether to print schedules as soon as they're found, unsorted, instead of all at the end.
+    #[serde(default)]
+    pub stream: bool,
+    /// Credits needed to graduate, counting taken classes and `taken_credits`. Schedules stop
+    /// adding terms once they have this many credits.
+    pub total_credits: Option<u16>,
+    /// Credits already earned which aren't from classes in both `taken.txt` and the catalog,
+    /// like transfer credits.
+    #[serde(default)]
+    pub taken_credits: u16,
+    /// Whether to only keep one of the partial schedules which took the same classes in a
+    /// different order, since they can be continued in exactly the same ways. Off by default,
+    /// since the orders merged away are never shown.
+    #[serde(default)]
+    pub merge_duplicates: bool,
+    /// Seed for sampling schedules, so the same schedules can be sampled again. Random if unset.
+    pub seed: Option<u64>,
+    /// How different sampled schedules must be from each other, from 0 to 1: the share of their
+    /// placements (a class in a term) which aren't in both.
+    #[serde(default = "sample_distance_fn")]
+    pub sample_distance: f64,
+    /// Most schedules to add a semester to before stopping the search.
+    pub max_nodes: Option<usize>,
+    /// Most seconds to search for before stopping.
+    pub time_limit: Option<u64>,
+    /// Most schedules to hold at once before stopping the search.
+    pub max_schedules: Option<usize>,
+    /// Whether to print how the search is going every second.
+    #[serde(default = "true_fn")]
+    pub progress: bool,
+    /// Threads to search with, defaulting to one per CPU core.
+    pub threads: Option<usize>,
+    /// Degree requirements from `degree.toml`, which replace `required` and `groups` when
+    /// deciding whether a schedule is complete.
+    #[serde(skip)]
+    pub degree: Option<Degree>,
+}
+
+/// Credits and courses a schedule needs from a group, and the most credits from it which count.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "GroupSpec")]
+pub struct Group {
+    pub credits: u8,
+    pub courses: u8,
+    pub max_credits: Option<u8>,
+}
+
+/// Groups can be written as just the minimum credits (`core = 6`) or as a table
+/// (`level400 = { max_credits = 6 }`, `labs = { courses = 2 }`).
+enum GroupSpec {
+    Credits(u8),
+    Table(GroupTable),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GroupTable {
+    #[serde(default)]
+    credits: u8,
+    #[serde(default)]
+    courses: u8,
+    max_credits: Option<u8>,
+}
+
+/// Written out rather than derived as an untagged enum, so a misspelled field in a table is
+/// reported by name instead of as a group which matches neither form.
+impl<'de> Deserialize<'de> for GroupSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SpecVisitor;
+
+        impl<'de> Visitor<'de> for SpecVisitor {
+            type Value = GroupSpec;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    f,
+                    "a number of credits or a table of credits, courses and max_credits"
+                )
+            }
+
+            fn visit_i64<E: de::Error>(self, credits: i64) -> Result<GroupSpec, E> {
+                u8::try_from(credits)
+                    .map(GroupSpec::Credits)
+                    .map_err(|_| E::invalid_value(Unexpected::Signed(credits), &self))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<GroupSpec, A::Error> {
+                GroupTable::deserialize(MapAccessDeserializer::new(map)).map(GroupSpec::Table)
+            }
+        }
+
+        deserializer.deserialize_any(SpecVisitor)
+    }
+}
+
+impl From<GroupSpec> for Group {
+    fn from(spec: GroupSpec) -> Self {
+        match spec {
+            GroupSpec::Credits(credits) => Group {
+                credits,
+                courses: 0,
+                max_credits: None,
+            },
+            GroupSpec::Table(GroupTable {
+                credits,
+                courses,
+                max_credits,
+            }) => Group {
+                credits,
+                courses,
+                max_credits,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups(toml: &str) -> Result<BTreeMap<String, Group>, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    #[test]
+    fn group_forms() {
+        let parsed =
+            groups("core = 6\nlevel400 = { max_credits = 6 }\nlabs = { courses = 2 }").unwrap();
+        assert_eq!(
+            parsed["core"],
+            Group {
+                credits: 6,
+                ..Group::default()
+            }
+        );
+        assert_eq!(parsed["level400"].max_credits, Some(6));
+        assert_eq!(parsed["labs"].courses, 2);
+    }
+
+    #[test]
+    fn misspelled_group_fields() {
+        let err = groups("level400 = { max_credit = 6 }").unwrap_err();
+        assert!(
+            err.message().contains("unknown field `max_credit`"),
+            "{err}"
+        );
+        assert!(groups("core = -1").is_err());
+    }
+
+    #[test]
+    fn shows_incomplete_by_default() {
+        assert!(crate::testing::config(3, 9, 2, "").show_incomplete);
+        assert!(!crate::testing::config(3, 9, 2, "show_incomplete = false").show_incomplete);
+    }
 }
@@ -1,35 +1,52 @@
-use std::collections::BTreeMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::{fmt::Display, rc::Rc};
+use std::{fmt::Display, sync::Arc};
 
 use crate::config::Config;
+use crate::degree::{Degree, Progress};
+use crate::groups;
 use crate::requirements::TestRequisite;
-use crate::{class::Class, requirements::RequisiteName};
+use crate::{
+    class::{self, Class},
+    requirements::RequisiteName,
+};
 
 use combinations::Combinations;
-use serde::Deserialize;
+use enum_iterator::Sequence;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use yansi::{Paint, Painted};
 
-pub struct Semester(pub Vec<Rc<Class>>, pub Term);
+pub struct Semester(pub Vec<Arc<Class>>, pub Term);
 
 #[derive(Error, Debug, PartialEq, Eq, Hash)]
 pub enum ScheduleError {
-    #[error("Too few credits")]
-    TooFewCredits,
-    #[error("Too many credits")]
-    TooManyCredits,
-    #[error("Not available in term {0}")]
-    NotAvailable(String),
-    #[error("Requisites for class un-met")]
-    RequisitesUnmet,
+    #[error("Too few credits in {0} ({1} < {2})")]
+    TooFewCredits(Term, u16, u8),
+    #[error("Too many credits in {0} ({1} > {2})")]
+    TooManyCredits(Term, u16, u8),
+    #[error("{0} not available in term {1}")]
+    NotAvailable(String, Term),
+    #[error("Requisites for {0} un-met in {1}: {}", .2.join(", "))]
+    RequisitesUnmet(String, Term, Vec<String>),
+    #[error("{0} must be taken in the same term as {1} ({2})")]
+    NotConcurrent(String, String, Term),
     #[error("Doesn't fulfill required courses")]
     RequirementsUnmet,
-    #[error("Did not meet credit requirement for group")]
-    GroupsUnmet,
+    #[error("Did not meet credit requirement for {}", .0.join(", "))]
+    GroupsUnmet(Vec<String>),
+    #[error("Did not meet course count requirement for {}", .0.join(", "))]
+    GroupCoursesUnmet(Vec<String>),
+    #[error("Did not meet degree requirements: {}", .0.join(", "))]
+    DegreeUnmet(Vec<String>),
+    #[error("Only {0} of {1} total credits")]
+    TotalCreditsUnmet(u16, u16),
 }
 
-#[derive(Debug, Copy, Clone, Deserialize)]
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Sequence,
+)]
 pub enum TermSeason {
     Spring,
     #[allow(unused)]
@@ -37,7 +54,7 @@ pub enum TermSeason {
     Fall,
 }
 
-#[derive(Debug, Copy, Clone, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Term {
     pub season: TermSeason,
     pub year: u16,
@@ -49,6 +66,19 @@ impl Display for Term {
     }
 }
 
+/// Terms are ordered by when they happen.
+impl Ord for Term {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.year, self.season).cmp(&(other.year, other.season))
+    }
+}
+
+impl PartialOrd for Term {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Term {
     pub fn new(season: TermSeason, year: u16) -> Self {
         Self { season, year }
@@ -69,13 +99,86 @@ impl Term {
         };
         if other.starts_with(&name) {
             if other.ends_with("Odd") {
-                return !(self.year % 2 == 0);
+                return !self.year.is_multiple_of(2);
             }
             if other.ends_with("Even") {
-                return self.year % 2 == 0;
+                return self.year.is_multiple_of(2);
             }
         }
-        return false;
+        false
+    }
+}
+
+impl ScheduleError {
+    /// What went wrong, without the details, so errors of the same kind can be counted together.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ScheduleError::TooFewCredits(..) => "Too few credits",
+            ScheduleError::TooManyCredits(..) => "Too many credits",
+            ScheduleError::NotAvailable(..) => "Class not available",
+            ScheduleError::RequisitesUnmet(..) => "Requisites un-met",
+            ScheduleError::NotConcurrent(..) => "Class not taken concurrently",
+            ScheduleError::RequirementsUnmet => "Doesn't fulfill required courses",
+            ScheduleError::GroupsUnmet(_) => "Did not meet credit requirement for group",
+            ScheduleError::GroupCoursesUnmet(_) => {
+                "Did not meet course count requirement for group"
+            }
+            ScheduleError::DegreeUnmet(_) => "Did not meet degree requirements",
+            ScheduleError::TotalCreditsUnmet(..) => "Not enough total credits",
+        }
+    }
+
+    /// The class responsible for the error and the term it was placed in, if it's down to a
+    /// single class.
+    pub fn class(&self) -> Option<(&str, Term)> {
+        match self {
+            ScheduleError::NotAvailable(class, term)
+            | ScheduleError::RequisitesUnmet(class, term, _)
+            | ScheduleError::NotConcurrent(class, _, term) => Some((class, *term)),
+            _ => None,
+        }
+    }
+
+    /// The term the error happened in, if it's down to a single term.
+    pub fn term(&self) -> Option<Term> {
+        match self {
+            ScheduleError::TooFewCredits(term, ..)
+            | ScheduleError::TooManyCredits(term, ..)
+            | ScheduleError::NotAvailable(_, term)
+            | ScheduleError::RequisitesUnmet(_, term, _)
+            | ScheduleError::NotConcurrent(_, _, term) => Some(*term),
+            ScheduleError::RequirementsUnmet
+            | ScheduleError::GroupsUnmet(_)
+            | ScheduleError::GroupCoursesUnmet(_)
+            | ScheduleError::DegreeUnmet(_)
+            | ScheduleError::TotalCreditsUnmet(..) => None,
+        }
+    }
+}
+
+/// How many errors of each kind were found, and how many came from each class in each term and
+/// from each term, without holding on to the errors themselves.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ErrorCounts {
+    pub by_kind: HashMap<&'static str, usize>,
+    pub by_class: HashMap<String, usize>,
+    pub by_term: HashMap<Term, usize>,
+    pub total: usize,
+}
+
+impl ErrorCounts {
+    pub fn add(&mut self, error: &ScheduleError) {
+        *self.by_kind.entry(error.kind()).or_default() += 1;
+        if let Some((class, term)) = error.class() {
+            *self
+                .by_class
+                .entry(format!("{class} in {term}"))
+                .or_default() += 1;
+        }
+        if let Some(term) = error.term() {
+            *self.by_term.entry(term).or_default() += 1;
+        }
+        self.total += 1;
     }
 }
 
@@ -85,196 +188,509 @@ impl Display for TermSeason {
     }
 }
 
+/// Schedules with the same state have taken the same classes for the same credits, only in
+/// different terms, so every way of continuing one is a way of continuing the other.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct ScheduleState {
+    term: Term,
+    taken: Vec<(String, u8)>,
+}
+
+#[derive(Clone)]
 pub struct Schedule {
-    remaining: Vec<Rc<Class>>,
-    semesters: Vec<Rc<Semester>>,
-    taken: Rc<Vec<String>>,
+    remaining: Vec<Arc<Class>>,
+    semesters: Vec<Arc<Semester>>,
+    taken: Arc<Vec<String>>,
+    /// The taken classes which are in the catalog.
+    taken_classes: Arc<Vec<Arc<Class>>>,
     first_term: Term,
+    /// Semesters from a saved plan, which don't count towards the semesters being planned.
+    locked: usize,
 }
 
 impl Semester {
-    pub fn new(classes: Vec<Rc<Class>>, term: Term) -> Self {
+    pub fn new(classes: Vec<Arc<Class>>, term: Term) -> Self {
         Semester(classes, term)
     }
 
     pub fn credits(&self) -> u16 {
-        self.0.iter().map(|class| class.credits as u16).sum()
+        self.0.iter().map(|class| class.credits() as u16).sum()
     }
 
-    pub fn verify(self, config: &Config) -> Result<Rc<Self>, ScheduleError> {
+    pub fn verify(self, config: &Config) -> Result<Arc<Self>, ScheduleError> {
         let credits = self.credits();
         if credits < config.min_credits.into() {
-            return Err(ScheduleError::TooFewCredits);
+            return Err(ScheduleError::TooFewCredits(
+                self.1,
+                credits,
+                config.min_credits,
+            ));
         }
         if credits > config.max_credits.into() {
-            return Err(ScheduleError::TooManyCredits);
+            return Err(ScheduleError::TooManyCredits(
+                self.1,
+                credits,
+                config.max_credits,
+            ));
         }
 
-        if !self.0.iter().all(|class| class.offered(&self.1)) {
-            return Err(ScheduleError::NotAvailable(self.1.to_string()));
+        if let Some(class) = self.0.iter().find(|class| !class.offered(&self.1)) {
+            return Err(ScheduleError::NotAvailable(class.name(), self.1));
         }
 
-        return Ok(Rc::new(self));
+        Ok(Arc::new(self))
     }
 }
 
-impl From<(Vec<Rc<Class>>, Term)> for Semester {
-    fn from((value, term): (Vec<Rc<Class>>, Term)) -> Self {
+impl From<(Vec<Arc<Class>>, Term)> for Semester {
+    fn from((value, term): (Vec<Arc<Class>>, Term)) -> Self {
         Semester::new(value, term)
     }
 }
 
 impl Display for Semester {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let names: Vec<String> = self.0.iter().map(|class| class.name()).collect();
+        let names: Vec<String> = self
+            .0
+            .iter()
+            .map(|class| match class.is_variable() {
+                true => format!("{} ({} cr)", class.name(), class.credits()),
+                false => class.name(),
+            })
+            .collect();
         write!(f, "{}", names.join(", "))
     }
 }
 
 impl Schedule {
-    pub fn new(classes: &Vec<Rc<Class>>, taken: Rc<Vec<String>>, term: Term) -> Self {
+    pub fn new(classes: &[Arc<Class>], taken: Arc<Vec<String>>, term: Term) -> Self {
+        let taken_classes = Arc::new(class::taken_classes(classes, &taken));
         let mut sched = Schedule {
             remaining: Vec::new(),
             semesters: Vec::new(),
             taken: taken.clone(),
+            taken_classes,
             first_term: term,
+            locked: 0,
         };
-        sched.remaining = classes.clone();
+        sched.remaining = classes
+            .iter()
+            .filter(|class| !class.used_up(&taken))
+            .cloned()
+            .collect();
         sched
     }
 
-    pub fn is_valid(&self) -> Result<(), ScheduleError> {
-        let mut classes = self.semesters.iter().map(|x| x.0.clone()).flatten();
-        if !classes.all(|class| class.requisites_met(self)) {
-            return Err(ScheduleError::RequisitesUnmet);
+    /// Checks the last semester, assuming the ones before it were checked when they were added.
+    /// Only [`Schedule::child`] should call this, so every semester is checked as it's added.
+    fn check_last_semester(&self) -> Result<(), ScheduleError> {
+        let Some(semester) = self.semesters.last() else {
+            return Ok(());
         };
 
-        return Ok(());
+        if let Some(class) = semester.0.iter().find(|class| !class.requisites_met(self)) {
+            return Err(ScheduleError::RequisitesUnmet(
+                class.name(),
+                semester.1,
+                class.unmet_requisites(self),
+            ));
+        }
+
+        // a class which has to be taken alongside one of these can't be taken later, unless it was
+        // just taken and is only still remaining since it can be repeated
+        for class in self
+            .remaining
+            .iter()
+            .filter(|class| !semester.0.contains(class))
+        {
+            for linked in class.linked() {
+                if semester.0.iter().any(|other| &other.name() == linked) {
+                    return Err(ScheduleError::NotConcurrent(
+                        class.name(),
+                        linked.clone(),
+                        semester.1,
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Everything about the schedule which decides how it can continue and whether it's complete.
+    pub fn state(&self) -> ScheduleState {
+        let mut taken: Vec<(String, u8)> = self
+            .semesters
+            .iter()
+            .flat_map(|sem| sem.0.iter())
+            .map(|class| (class.name(), class.credits()))
+            .collect();
+        taken.sort_unstable();
+        ScheduleState {
+            term: self.next_term(),
+            taken,
+        }
+    }
+
+    /// Each class paired with the term it's scheduled in.
+    pub fn placements(&self) -> HashSet<(String, Term)> {
+        self.semesters
+            .iter()
+            .flat_map(|sem| sem.0.iter().map(|class| (class.name(), sem.1)))
+            .collect()
     }
 
     pub fn total_credits(&self) -> u16 {
         self.semesters
             .iter()
-            .map(|x| x.0.clone())
-            .flatten()
-            .map(|x| x.credits as u16)
+            .flat_map(|x| x.0.clone())
+            .map(|x| x.credits() as u16)
             .sum()
     }
 
-    pub fn meets_group_credits(&self, config: &Config) -> bool {
-        let classes = self.semesters.iter().map(|x| x.0.clone()).flatten();
-        let mut groups: BTreeMap<String, u8> = config.groups.clone();
-        groups.values_mut().for_each(|v| *v = 0);
+    /// Credits earned so far: the taken classes in the catalog, `taken_credits` from the config
+    /// and every class scheduled.
+    pub fn earned_credits(&self, config: &Config) -> u16 {
+        self.taken_credits() + config.taken_credits + self.total_credits()
+    }
 
-        // this is definitely not efficient lol
-        for class in classes {
-            for group in class.groups() {
-                let group_val = groups
-                    .get_mut(group)
-                    .expect("Class has a group which is not present in config");
-                *group_val += class.credits;
-            }
-        }
-        // safety: will keys always be in same order? we're cloning, so it seems like yes, but not actually doing anything to ensure that
-        groups
+    /// Whether the schedule has the credits to graduate, so it doesn't need any more terms, even
+    /// if it's missing something else.
+    pub fn reached_total_credits(&self, config: &Config) -> bool {
+        config
+            .total_credits
+            .is_some_and(|target| self.earned_credits(config) >= target)
+    }
+
+    /// Whether the schedule stops before `semesters` terms: it has the credits to graduate, or
+    /// every class has been taken as many times as it can be, so there's nothing left to add.
+    pub fn finished(&self, config: &Config) -> bool {
+        self.remaining.is_empty() || self.reached_total_credits(config)
+    }
+
+    /// The groups whose credit requirements aren't met, if any.
+    pub fn meets_group_credits(&self, config: &Config) -> Result<(), Vec<String>> {
+        let classes: Vec<Arc<Class>> = self.semesters.iter().flat_map(|x| x.0.clone()).collect();
+        groups::groups_met(&classes, config, false)
+    }
+
+    /// The groups whose credit or course count requirements aren't met, if any.
+    pub fn meets_group_courses(&self, config: &Config) -> Result<(), Vec<String>> {
+        let classes: Vec<Arc<Class>> = self.semesters.iter().flat_map(|x| x.0.clone()).collect();
+        groups::groups_met(&classes, config, true)
+    }
+
+    /// Progress through each program with the classes taken and scheduled so far. Taken classes
+    /// in the catalog count for their credits and groups like scheduled ones.
+    pub fn audit(&self, degree: &Degree) -> Vec<Progress> {
+        let classes: Vec<Arc<Class>> = self
+            .taken_classes
             .iter()
-            .zip(&config.groups)
-            .all(|(this, other)| this.1 >= other.1)
+            .cloned()
+            .chain(self.semesters.iter().flat_map(|x| x.0.clone()))
+            .collect();
+        let taken: Vec<String> = self
+            .taken
+            .iter()
+            .filter(|name| {
+                !self
+                    .taken_classes
+                    .iter()
+                    .any(|class| class.name() == **name)
+            })
+            .cloned()
+            .collect();
+        degree.audit(&classes, &taken)
     }
 
     pub fn is_complete(&self, config: &Config) -> Result<(), ScheduleError> {
-        if self.remaining.iter().any(|class| class.required) {
+        if let Some(target) = config.total_credits {
+            let credits = self.earned_credits(config);
+            if credits < target {
+                return Err(ScheduleError::TotalCreditsUnmet(credits, target));
+            }
+        }
+
+        if let Some(degree) = &config.degree {
+            let progress = self.audit(degree);
+            let unmet: Vec<String> = match progress.as_slice() {
+                [program] if !program.met => {
+                    program.unmet().into_iter().map(str::to_owned).collect()
+                }
+                _ => progress
+                    .iter()
+                    .filter(|program| !program.met)
+                    .map(|program| match program.unmet().as_slice() {
+                        [name] if *name == program.name => program.name.clone(),
+                        blocks => format!("{} ({})", program.name, blocks.join(", ")),
+                    })
+                    .collect(),
+            };
+            if !unmet.is_empty() {
+                return Err(ScheduleError::DegreeUnmet(unmet));
+            }
+            return Ok(());
+        }
+
+        if self
+            .remaining
+            .iter()
+            .any(|class| class.required && self.times_taken(class) == 0)
+        {
             return Err(ScheduleError::RequirementsUnmet);
         };
-        if !self.meets_group_credits(&config) {
-            return Err(ScheduleError::GroupsUnmet);
-        }
-        Ok(())
+        self.meets_group_credits(config)
+            .map_err(ScheduleError::GroupsUnmet)?;
+        self.meets_group_courses(config)
+            .map_err(ScheduleError::GroupCoursesUnmet)
     }
 
-    pub fn completeness_display(&self, config: &Config) -> Painted<&str> {
-        match self.is_complete(&config) {
-            Ok(_) => "Yes".green(),
-            Err(ScheduleError::RequirementsUnmet) => "No, requirements unmet".red(),
-            Err(ScheduleError::GroupsUnmet) => "No, group credit requirement unmet".red(),
+    pub fn completeness_display(&self, config: &Config) -> Painted<String> {
+        let reason = match self.is_complete(config) {
+            Ok(_) => return Painted::new("Yes".to_owned()).green(),
+            Err(ScheduleError::RequirementsUnmet) => "requirements unmet".to_owned(),
+            Err(ScheduleError::GroupsUnmet(groups)) => {
+                format!("group credit requirement unmet: {}", groups.join(", "))
+            }
+            Err(ScheduleError::GroupCoursesUnmet(groups)) => {
+                format!("group course count unmet: {}", groups.join(", "))
+            }
+            Err(ScheduleError::DegreeUnmet(blocks)) => {
+                format!("degree requirements unmet: {}", blocks.join(", "))
+            }
+            Err(ScheduleError::TotalCreditsUnmet(credits, target)) => {
+                format!("total credits unmet ({credits}/{target})")
+            }
             Err(_) => panic!("Unknown completeness error"),
+        };
+        Painted::new(format!("No, {reason}")).red()
+    }
+
+    pub fn child(&self, semester: Arc<Semester>) -> Result<Schedule, ScheduleError> {
+        let new = self.with_semester(semester);
+
+        match new.check_last_semester() {
+            Ok(_) => Ok(new),
+            Err(err) => Err(err),
         }
     }
 
-    pub fn child(&self, semester: Rc<Semester>) -> Result<Schedule, ScheduleError> {
-        let remaining: Vec<Rc<Class>> = self
+    /// Adds a semester without checking that requisites are met.
+    pub fn with_semester(&self, semester: Arc<Semester>) -> Schedule {
+        let mut semesters: Vec<Arc<Semester>> = self.semesters.clone();
+        semesters.push(semester.clone());
+
+        // repeatable classes stay available until they've been taken as many times as allowed
+        let remaining: Vec<Arc<Class>> = self
             .remaining
             .clone()
             .into_iter()
-            .filter(|class| !semester.0.contains(class))
+            .filter(|class| {
+                !semester.0.contains(class)
+                    || semesters.iter().filter(|sem| sem.0.contains(class)).count()
+                        < class.repeats().into()
+            })
             .collect();
 
-        let mut semesters: Vec<Rc<Semester>> = self.semesters.clone();
-        semesters.push(semester.clone());
-
-        let new = Schedule {
+        Schedule {
             remaining,
             semesters,
             taken: self.taken.clone(),
+            taken_classes: self.taken_classes.clone(),
             first_term: self.first_term,
-        };
+            locked: self.locked,
+        }
+    }
 
-        match new.is_valid() {
-            Ok(_) => Ok(new),
-            Err(err) => Err(err),
+    /// Number of semesters `class` has been taken in so far.
+    pub fn times_taken(&self, class: &Class) -> usize {
+        self.semesters
+            .iter()
+            .filter(|sem| sem.0.iter().any(|other| **other == *class))
+            .count()
+    }
+
+    /// Keeps the semesters so far as they are, planning `semesters` more after them.
+    pub fn lock(mut self) -> Self {
+        self.locked = self.semesters.len();
+        self
+    }
+
+    pub fn semesters(&self) -> &[Arc<Semester>] {
+        &self.semesters
+    }
+
+    pub fn taken(&self) -> &[String] {
+        &self.taken
+    }
+
+    /// The taken classes which are in the catalog.
+    pub fn taken_classes(&self) -> &[Arc<Class>] {
+        &self.taken_classes
+    }
+
+    /// Credits for the taken classes which are in the catalog.
+    pub fn taken_credits(&self) -> u16 {
+        self.taken_classes
+            .iter()
+            .map(|class| class.credits() as u16)
+            .sum()
+    }
+
+    pub fn remaining(&self) -> &[Arc<Class>] {
+        &self.remaining
+    }
+
+    pub fn next_term(&self) -> Term {
+        match self.semesters.last() {
+            Some(sem) => sem.1.next(),
+            None => self.first_term,
+        }
+    }
+
+    /// Remaining classes which could be taken in `term`: offered then, and with their
+    /// requisites met if every other such class were taken alongside them.
+    fn available(&self, term: Term) -> Vec<Arc<Class>> {
+        let mut available: Vec<Arc<Class>> = self
+            .remaining
+            .iter()
+            .filter(|class| class.offered(&term))
+            .cloned()
+            .collect();
+        loop {
+            let together = self.with_semester(Arc::new(Semester::new(available.clone(), term)));
+            let before = available.len();
+            available.retain(|class| class.requisites_met(&together));
+            if available.len() == before {
+                return available;
+            }
         }
     }
 
-    pub fn generate_possible(&self, config: &Config) -> Vec<Result<Rc<Semester>, ScheduleError>> {
-        let mut sorted = self.remaining.clone();
-        sorted.sort_unstable_by_key(|x| x.credits);
+    /// Available classes grouped so that bundled classes are picked together. Bundles with a
+    /// class which isn't available are left out entirely.
+    fn units(&self, available: &[Arc<Class>]) -> Vec<Vec<Arc<Class>>> {
+        let mut units: Vec<Vec<Arc<Class>>> = class::units(available)
+            .into_iter()
+            .map(|unit| unit.into_iter().map(|i| available[i].clone()).collect())
+            .collect();
+        units.retain(|unit| match unit[0].bundle() {
+            Some(bundle) => {
+                self.remaining
+                    .iter()
+                    .filter(|class| class.bundle() == Some(bundle))
+                    .count()
+                    == unit.len()
+            }
+            None => true,
+        });
+        units
+    }
+
+    /// Required classes which won't be offered again before the last semester being planned,
+    /// so have to be taken in `term` for the schedule to be complete.
+    fn last_chances(&self, term: Term, config: &Config) -> Vec<Arc<Class>> {
+        let planned = self.semesters.len() - self.locked;
+        let terms_left = (config.semesters as usize).saturating_sub(planned + 1);
+        let later: Vec<Term> = std::iter::successors(Some(term.next()), |term| Some(term.next()))
+            .take(terms_left)
+            .collect();
+        self.remaining
+            .iter()
+            .filter(|class| class.required && self.times_taken(class) == 0)
+            .filter(|class| !later.iter().any(|term| class.offered(term)))
+            .cloned()
+            .collect()
+    }
+
+    pub fn generate_possible(&self, config: &Config) -> Vec<Result<Arc<Semester>, ScheduleError>> {
+        let term = self.next_term();
+        let available = self.available(term);
+
+        // schedules which can't be complete are only worth finishing if they're being shown
+        let forced = match config.show_incomplete || config.degree.is_some() {
+            true => Vec::new(),
+            false => self.last_chances(term, config),
+        };
+        if forced.iter().any(|class| !available.contains(class)) {
+            return Vec::new();
+        }
+
+        let units = self.units(&available);
+        if units.is_empty() {
+            return Vec::new();
+        }
+        // variable-credit classes are as small as possible when fitting the most classes in, and
+        // as large as possible when fitting the fewest
+        let least_credits = |unit: &Vec<Arc<Class>>| -> u16 {
+            unit.iter().map(|class| class.credit_range().0 as u16).sum()
+        };
+        let most_credits = |unit: &Vec<Arc<Class>>| -> u16 {
+            unit.iter().map(|class| class.credit_range().1 as u16).sum()
+        };
+        let mut sorted = units.clone();
+        sorted.sort_unstable_by_key(least_credits);
 
         let mut accum = 0;
         let mut max = 0;
         for val in sorted.iter() {
             max += 1;
-            accum += val.credits;
-            if accum >= config.max_credits {
+            accum += least_credits(val);
+            if accum >= config.max_credits.into() {
                 break;
             }
         }
-        max = std::cmp::min(max, self.remaining.len() - 1);
+        max = std::cmp::min(max, units.len() - 1);
 
         accum = 0;
-        sorted.reverse();
+        sorted.sort_unstable_by_key(|unit| std::cmp::Reverse(most_credits(unit)));
         let mut min = 0;
-        for x in sorted {
+        for x in sorted.iter() {
             min += 1;
-            accum += x.credits;
-            if accum >= config.min_credits {
+            accum += most_credits(x);
+            if accum >= config.min_credits.into() {
                 break;
             }
         }
 
-        let term = match self.semesters.last() {
-            Some(sem) => sem.1.next(),
-            None => self.first_term,
-        };
-
-        // try subsets of remaining classes and all remaining classes
-        let mut candidates: Vec<Semester> = (min..=max)
-            .into_iter()
-            .map(|i| Combinations::new(self.remaining.clone(), i))
-            .flatten()
-            .map(|x| (x, term).into())
+        // try subsets of available classes and all available classes, with bundles as one class
+        let mut candidates: Vec<Vec<Arc<Class>>> = (min..=max)
+            .flat_map(|i| Combinations::new(units.clone(), i))
+            .map(|x| x.concat())
             .collect();
-        let remaining_credits: u16 = self.remaining.iter().map(|x| x.credits as u16).sum();
-        if remaining_credits <= config.max_credits.into() {
-            candidates.push((self.remaining.clone(), term).into());
+        let all: Vec<Arc<Class>> = units.concat();
+        let all_credits: u16 = all.iter().map(|x| x.credits() as u16).sum();
+        if all_credits <= config.max_credits.into() {
+            candidates.push(all);
         };
 
         candidates
             .into_iter()
+            .filter(|classes| forced.iter().all(|class| classes.contains(class)))
+            .flat_map(credit_choices)
+            .map(|x| Semester::new(x, term))
             .map(|x: Semester| x.verify(config))
             .collect()
     }
 }
 
+/// Every way of picking credits for the variable-credit classes in `classes`.
+fn credit_choices(classes: Vec<Arc<Class>>) -> Vec<Vec<Arc<Class>>> {
+    if !classes.iter().any(|class| class.is_variable()) {
+        return vec![classes];
+    }
+    classes
+        .iter()
+        .map(|class| match class.credit_range() {
+            (min, max) if min != max => (min..=max)
+                .map(|credits| Arc::new(class.with_credits(credits)))
+                .collect(),
+            _ => vec![class.clone()],
+        })
+        .multi_cartesian_product()
+        .collect()
+}
+
 impl TestRequisite for Schedule {
     fn has_prerequisite(&self, requisite: &RequisiteName) -> bool {
         if self.taken.contains(requisite) {
@@ -283,8 +699,7 @@ impl TestRequisite for Schedule {
 
         if let Some((_, sems)) = self.semesters.split_last() {
             sems.iter()
-                .map(|x| x.0.clone())
-                .flatten()
+                .flat_map(|x| x.0.clone())
                 .any(|class| requisite == &class.name())
         } else {
             false
@@ -298,10 +713,21 @@ impl TestRequisite for Schedule {
 
         self.semesters
             .iter()
-            .map(|x| x.0.clone())
-            .flatten()
+            .flat_map(|x| x.0.clone())
             .any(|class| requisite == &class.name())
     }
+
+    fn has_concurrent(&self, requisite: &RequisiteName) -> bool {
+        // there's no telling which term a taken class was taken in, so it counts like it does
+        // for any other requisite
+        if self.taken.contains(requisite) {
+            return true;
+        }
+
+        self.semesters
+            .last()
+            .is_some_and(|sem| sem.0.iter().any(|class| requisite == &class.name()))
+    }
 }
 
 impl Display for Schedule {
@@ -336,3 +762,294 @@ impl Display for Schedule {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::testing::{classes, config, every_schedule, layout, root, taken};
+
+    fn layouts(rows: &str, taken_classes: &[&str]) -> Vec<Vec<Vec<String>>> {
+        let config = config(1, 10, 2, "");
+        let classes = classes(rows);
+        every_schedule(root(&classes, &taken(taken_classes), &config), &config)
+            .iter()
+            .map(layout)
+            .sorted()
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn concurrent_pairs() {
+        // the lab needs the lecture in the same term, and the lecture can't go ahead without it
+        let layouts = layouts(
+            "PHY,183,4,false,,Fall|Spring,
+             PHY,191,1,false,,Fall|Spring,PHY 183 concurrently
+             CSE,100,3,false,,Fall|Spring,",
+            &[],
+        );
+        assert_eq!(
+            layouts,
+            [
+                vec![names(&["CSE 100"]), names(&["PHY 183", "PHY 191"])],
+                vec![names(&["CSE 100", "PHY 183", "PHY 191"])],
+                vec![names(&["PHY 183", "PHY 191"]), names(&["CSE 100"])],
+            ]
+        );
+    }
+
+    #[test]
+    fn concurrent_pairs_both_ways() {
+        let layouts = layouts(
+            "PHY,183,4,false,,Fall|Spring,PHY 191 concurrently
+             PHY,191,1,false,,Fall|Spring,PHY 183 concurrently
+             CSE,100,3,false,,Fall|Spring,",
+            &[],
+        );
+        assert_eq!(
+            layouts,
+            [
+                vec![names(&["CSE 100"]), names(&["PHY 183", "PHY 191"])],
+                vec![names(&["CSE 100", "PHY 183", "PHY 191"])],
+                vec![names(&["PHY 183", "PHY 191"]), names(&["CSE 100"])],
+            ]
+        );
+    }
+
+    #[test]
+    fn concurrent_with_taken() {
+        // the lecture was taken before, so the lab can be taken on its own
+        let layouts = layouts(
+            "PHY,191,1,false,,Fall|Spring,PHY 183 concurrently
+             CSE,100,3,false,,Fall|Spring,",
+            &["PHY 183"],
+        );
+        assert_eq!(
+            layouts,
+            [
+                vec![names(&["CSE 100"]), names(&["PHY 191"])],
+                vec![names(&["CSE 100", "PHY 191"])],
+                vec![names(&["PHY 191"]), names(&["CSE 100"])],
+            ]
+        );
+    }
+
+    #[test]
+    fn concurrent_with_repeats() {
+        // the lab can be repeated, so it's still remaining once it's taken with the lecture
+        let layouts = layouts(
+            "PHY,183,4,false,,Fall|Spring,
+             PHY,191,1,false,,Fall|Spring,PHY 183 concurrently,,2
+             CSE,100,3,false,,Fall|Spring,",
+            &[],
+        );
+        assert_eq!(
+            layouts,
+            [
+                vec![names(&["CSE 100"]), names(&["PHY 183", "PHY 191"])],
+                vec![names(&["PHY 183", "PHY 191"]), names(&["CSE 100"])],
+            ]
+        );
+    }
+
+    #[test]
+    fn bundles() {
+        // the lab is only offered in the spring, so the pair can only be taken then
+        let layouts = layouts(
+            "PHY,183,4,false,,Fall|Spring,,physics
+             PHY,191,1,false,,Spring,,physics
+             CSE,100,3,false,,Fall|Spring,
+             CSE,101,3,false,,Fall|Spring,",
+            &[],
+        );
+        assert_eq!(
+            layouts,
+            [
+                vec![names(&["CSE 100"]), names(&["CSE 101"])],
+                vec![
+                    names(&["CSE 100"]),
+                    names(&["CSE 101", "PHY 183", "PHY 191"])
+                ],
+                vec![names(&["CSE 100"]), names(&["PHY 183", "PHY 191"])],
+                vec![
+                    names(&["CSE 100", "CSE 101"]),
+                    names(&["PHY 183", "PHY 191"])
+                ],
+                vec![names(&["CSE 101"]), names(&["CSE 100"])],
+                vec![
+                    names(&["CSE 101"]),
+                    names(&["CSE 100", "PHY 183", "PHY 191"])
+                ],
+                vec![names(&["CSE 101"]), names(&["PHY 183", "PHY 191"])],
+            ]
+        );
+    }
+
+    /// Each semester's classes with the credits they're taken for.
+    fn credit_layouts(rows: &str, semesters: u8, min: u8, max: u8) -> Vec<Vec<Vec<(String, u8)>>> {
+        let config = config(min, max, semesters, "");
+        let classes = classes(rows);
+        every_schedule(root(&classes, &taken(&[]), &config), &config)
+            .iter()
+            .map(|sched| {
+                sched
+                    .semesters()
+                    .iter()
+                    .map(|sem| {
+                        sem.0
+                            .iter()
+                            .map(|class| (class.name(), class.credits()))
+                            .sorted()
+                            .collect()
+                    })
+                    .collect()
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn variable_credits() {
+        let layouts = credit_layouts(
+            "XYZ,490,1-4,false,,Fall|Spring,
+             CSE,100,3,false,,Fall|Spring,",
+            1,
+            4,
+            6,
+        );
+        let xyz = |credits| ("XYZ 490".to_owned(), credits);
+        let cse = ("CSE 100".to_owned(), 3);
+        // every number of credits which keeps the semester within its limits
+        assert_eq!(
+            layouts,
+            [
+                vec![vec![cse.clone(), xyz(1)]],
+                vec![vec![cse.clone(), xyz(2)]],
+                vec![vec![cse, xyz(3)]],
+                vec![vec![xyz(4)]],
+            ]
+        );
+    }
+
+    #[test]
+    fn repeats() {
+        // once in each term, for any number of credits each time
+        let layouts = credit_layouts("XYZ,490,1-2,false,,Fall|Spring,,,2", 2, 1, 2);
+        let xyz = |credits| vec![("XYZ 490".to_owned(), credits)];
+        assert_eq!(
+            layouts,
+            [
+                vec![xyz(1), xyz(1)],
+                vec![xyz(1), xyz(2)],
+                vec![xyz(2), xyz(1)],
+                vec![xyz(2), xyz(2)],
+            ]
+        );
+    }
+
+    /// Every schedule found without leaving anything out before semesters are put together:
+    /// every set of remaining classes is tried in every term, and only rejected afterwards.
+    fn unpruned(root: super::Schedule, config: &crate::config::Config) -> Vec<super::Schedule> {
+        let mut scheds = vec![root];
+        let mut finished = Vec::new();
+        for _ in 0..config.semesters {
+            let (done, open): (Vec<_>, Vec<_>) =
+                scheds.into_iter().partition(|sched| sched.finished(config));
+            finished.extend(done);
+            scheds = open
+                .iter()
+                .flat_map(|sched| {
+                    let remaining = sched.remaining();
+                    crate::class::units(remaining)
+                        .into_iter()
+                        .powerset()
+                        .flat_map(|units| {
+                            super::credit_choices(
+                                units
+                                    .concat()
+                                    .into_iter()
+                                    .map(|i| remaining[i].clone())
+                                    .collect(),
+                            )
+                        })
+                        .filter_map(|classes| {
+                            super::Semester::new(classes, sched.next_term())
+                                .verify(config)
+                                .and_then(|sem| sched.child(sem))
+                                .ok()
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+        }
+        scheds.extend(finished);
+        scheds
+    }
+
+    #[test]
+    fn pruning_keeps_schedules() {
+        let classes = classes(
+            "ABC,100,3,true,,Fall,
+             ABC,101,3,true,,Spring,ABC 100
+             ABC,102,2,false,,Fall|Spring,
+             ABC,103,1-2,false,,Fall|Spring,ABC 102 or concurrently
+             ABC,104,3,true,,Fall,
+             ABC,105,2,false,,Spring,,lab
+             ABC,106,1,false,,Spring,,lab",
+        );
+        let credit_layout = |sched: &super::Schedule| -> Vec<Vec<(String, u8)>> {
+            sched
+                .semesters()
+                .iter()
+                .map(|sem| {
+                    sem.0
+                        .iter()
+                        .map(|class| (class.name(), class.credits()))
+                        .sorted()
+                        .collect()
+                })
+                .collect()
+        };
+        for show_incomplete in [false, true] {
+            let config = config(3, 7, 3, &format!("show_incomplete = {show_incomplete}"));
+            let root = root(&classes, &taken(&[]), &config);
+            let shown = |scheds: Vec<super::Schedule>| -> Vec<Vec<Vec<(String, u8)>>> {
+                scheds
+                    .iter()
+                    .filter(|sched| show_incomplete || sched.is_complete(&config).is_ok())
+                    .map(credit_layout)
+                    .sorted()
+                    .collect()
+            };
+            let pruned = shown(every_schedule(root.clone(), &config));
+            assert!(!pruned.is_empty());
+            assert_eq!(pruned, shown(unpruned(root, &config)));
+        }
+    }
+
+    #[test]
+    fn error_counts() {
+        use super::{ErrorCounts, ScheduleError, Term, TermSeason};
+
+        let fall = Term::new(TermSeason::Fall, 2024);
+        let mut counts = ErrorCounts::default();
+        for error in [
+            ScheduleError::NotAvailable("ABC 100".to_owned(), fall),
+            ScheduleError::NotAvailable("ABC 100".to_owned(), fall),
+            ScheduleError::TooFewCredits(fall, 1, 3),
+            ScheduleError::RequirementsUnmet,
+        ] {
+            counts.add(&error);
+        }
+        assert_eq!(counts.total, 4);
+        assert_eq!(counts.by_kind["Class not available"], 2);
+        assert_eq!(counts.by_kind.len(), 3);
+        assert_eq!(counts.by_class["ABC 100 in Fall 2024"], 2);
+        assert_eq!(counts.by_class.len(), 1);
+        assert_eq!(counts.by_term[&fall], 3);
+    }
+}
@@ -0,0 +1,701 @@
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use microlp::{ComparisonOp, OptimizationDirection, Problem, Variable};
+use serde::Deserialize;
+use yansi::Paint;
+
+use crate::class::Class;
+
+/// A block of degree requirements from `degree.toml`. A block is met when every part of it which
+/// is filled in is met: all of `all`, at least one of `any`, every course in `courses`, and
+/// `count` courses and `credits` credits from the courses in `from` or in `group`. A course can
+/// count towards more than one block.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Requirement {
+    pub name: String,
+    #[serde(default)]
+    pub all: Vec<Requirement>,
+    #[serde(default)]
+    pub any: Vec<Requirement>,
+    #[serde(default)]
+    pub courses: Vec<String>,
+    #[serde(default)]
+    pub from: Vec<String>,
+    pub group: Option<String>,
+    #[serde(default)]
+    pub count: u8,
+    #[serde(default)]
+    pub credits: u8,
+}
+
+/// Every program being worked towards at once, like a major, a minor and general education.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(from = "DegreeFile")]
+pub struct Degree {
+    pub programs: Vec<Requirement>,
+    /// Whether a course can count towards more than one program.
+    pub double_count: bool,
+}
+
+/// `degree.toml` holds either a single program's requirements, or a list of programs.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DegreeFile {
+    Programs {
+        programs: Vec<Requirement>,
+        #[serde(default)]
+        double_count: bool,
+    },
+    Single(Requirement),
+}
+
+impl From<DegreeFile> for Degree {
+    fn from(file: DegreeFile) -> Self {
+        match file {
+            DegreeFile::Programs {
+                programs,
+                double_count,
+            } => Degree {
+                programs,
+                double_count,
+            },
+            DegreeFile::Single(program) => Degree {
+                programs: vec![program],
+                double_count: true,
+            },
+        }
+    }
+}
+
+/// How far a schedule is through a block of requirements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub name: String,
+    pub met: bool,
+    /// Courses from `courses` which haven't been taken.
+    pub missing: Vec<String>,
+    /// Courses and credits counted towards `count` and `credits`, if the block has them.
+    pub counted: Option<(u16, u16)>,
+    pub needed: (u8, u8),
+    pub children: Vec<Progress>,
+}
+
+impl Requirement {
+    /// Checks the block against `classes`, and the taken classes in `taken` which aren't in the
+    /// catalog. Those only count by name, so they count as courses but not credits or groups.
+    pub fn audit(&self, classes: &[Arc<Class>], taken: &[String]) -> Progress {
+        let done =
+            |name: &String| taken.contains(name) || classes.iter().any(|c| &c.name() == name);
+        let missing: Vec<String> = self
+            .courses
+            .iter()
+            .filter(|name| !done(name))
+            .cloned()
+            .collect();
+
+        let counted = (self.count > 0 || self.credits > 0).then(|| {
+            let matching = classes.iter().filter(|class| {
+                self.from.contains(&class.name())
+                    || self
+                        .group
+                        .as_ref()
+                        .is_some_and(|group| class.groups().contains(&group.as_str()))
+            });
+            let (courses, credits) = matching.fold((0, 0), |(courses, credits), class| {
+                (courses + 1, credits + class.credits() as u16)
+            });
+            let taken = self
+                .from
+                .iter()
+                .filter(|name| taken.contains(name) && !classes.iter().any(|c| &c.name() == *name))
+                .count() as u16;
+            (courses + taken, credits)
+        });
+
+        let all: Vec<Progress> = self
+            .all
+            .iter()
+            .map(|req| req.audit(classes, taken))
+            .collect();
+        let any: Vec<Progress> = self
+            .any
+            .iter()
+            .map(|req| req.audit(classes, taken))
+            .collect();
+
+        let met = missing.is_empty()
+            && counted.is_none_or(|(courses, credits)| {
+                courses >= self.count.into() && credits >= self.credits.into()
+            })
+            && all.iter().all(|progress| progress.met)
+            && (any.is_empty() || any.iter().any(|progress| progress.met));
+
+        Progress {
+            name: self.name.clone(),
+            met,
+            missing,
+            counted,
+            needed: (self.count, self.credits),
+            children: all.into_iter().chain(any).collect(),
+        }
+    }
+
+    /// Whether the class could count towards anything in the block.
+    fn mentions(&self, name: &String, groups: &[&str]) -> bool {
+        self.courses.contains(name)
+            || self.from.contains(name)
+            || self
+                .group
+                .as_ref()
+                .is_some_and(|group| groups.contains(&group.as_str()))
+            || self
+                .all
+                .iter()
+                .chain(self.any.iter())
+                .any(|req| req.mentions(name, groups))
+    }
+
+    /// Every course named anywhere in the block.
+    pub fn courses(&self) -> Vec<&String> {
+        self.courses
+            .iter()
+            .chain(self.from.iter())
+            .chain(
+                self.all
+                    .iter()
+                    .chain(self.any.iter())
+                    .flat_map(|req| req.courses()),
+            )
+            .collect()
+    }
+}
+
+impl Degree {
+    /// Progress through each program. When courses can't double count, each course shared
+    /// between programs is counted towards whichever program lets the most programs be met.
+    pub fn audit(&self, classes: &[Arc<Class>], taken: &[String]) -> Vec<Progress> {
+        if self.double_count || self.programs.len() < 2 {
+            return self
+                .programs
+                .iter()
+                .map(|program| program.audit(classes, taken))
+                .collect();
+        }
+
+        let candidates = self.candidates(classes, taken);
+        let mut sharing = Sharing {
+            degree: self,
+            classes,
+            taken,
+            best: None,
+            tried: 0,
+        };
+        // only courses which more than one program wants need a choice made
+        let shared: Vec<usize> = (0..candidates.len())
+            .filter(|&course| candidates[course].len() > 1)
+            .collect();
+        let mut owners = candidates.clone();
+        sharing.assign(&mut owners, &candidates, &shared);
+        // the search is exact unless it gave up before meeting every program
+        if sharing.tried >= SHARING_LIMIT
+            && sharing
+                .best
+                .as_ref()
+                .is_none_or(|(best, _)| *best < self.programs.len())
+        {
+            if let Some(owners) = sharing.exact(&candidates) {
+                return sharing.progress(&owners);
+            }
+        }
+        sharing
+            .best
+            .map(|(_, progress)| progress)
+            .unwrap_or_default()
+    }
+
+    /// The programs each scheduled class and then each taken course could count towards.
+    fn candidates(&self, classes: &[Arc<Class>], taken: &[String]) -> Vec<Vec<usize>> {
+        classes
+            .iter()
+            .map(|class| self.mentioning(&class.name(), &class.groups()))
+            .chain(taken.iter().map(|name| self.mentioning(name, &[])))
+            .collect()
+    }
+
+    /// Indexes of the programs which the course could count towards.
+    pub fn mentioning(&self, name: &String, groups: &[&str]) -> Vec<usize> {
+        self.programs
+            .iter()
+            .enumerate()
+            .filter(|(_, program)| program.mentions(name, groups))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Every course named anywhere in any program.
+    pub fn courses(&self) -> Vec<&String> {
+        self.programs
+            .iter()
+            .flat_map(|program| program.courses())
+            .collect()
+    }
+}
+
+/// Choices tried when sharing courses between programs, after which the courses are shared out
+/// with an integer program instead.
+const SHARING_LIMIT: usize = 1000;
+
+/// A search for the programs to count shared courses towards, when they can't double count.
+/// Programs are only ever met by more courses, so the search gives up on a choice once counting
+/// every undecided course towards every program it could go to meets no more programs than the
+/// best found so far, and only chooses between programs which still need the course. The first
+/// choices tried give each course to the first program still needing it, which is usually enough.
+/// The search stops after [`SHARING_LIMIT`] choices, and if it hasn't met every program by then,
+/// [`Sharing::exact`] finds the best choices instead.
+struct Sharing<'a> {
+    degree: &'a Degree,
+    classes: &'a [Arc<Class>],
+    taken: &'a [String],
+    /// Programs met and the progress, for the best choices found so far.
+    best: Option<(usize, Vec<Progress>)>,
+    tried: usize,
+}
+
+impl Sharing<'_> {
+    /// Progress when each course counts towards the programs in `owners`, with scheduled classes
+    /// first and then taken courses.
+    fn progress(&self, owners: &[Vec<usize>]) -> Vec<Progress> {
+        let (class_owners, taken_owners) = owners.split_at(self.classes.len());
+        self.degree
+            .programs
+            .iter()
+            .enumerate()
+            .map(|(index, program)| {
+                let classes: Vec<Arc<Class>> = self
+                    .classes
+                    .iter()
+                    .zip(class_owners)
+                    .filter(|(_, owners)| owners.contains(&index))
+                    .map(|(class, _)| class.clone())
+                    .collect();
+                let taken: Vec<String> = self
+                    .taken
+                    .iter()
+                    .zip(taken_owners)
+                    .filter(|(_, owners)| owners.contains(&index))
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                program.audit(&classes, &taken)
+            })
+            .collect()
+    }
+
+    /// The programs to count each course towards to meet as many programs as possible, found
+    /// with an integer program: a variable for whether each course counts towards each program
+    /// which could use it, with each course counting towards at most one of them.
+    fn exact(&self, candidates: &[Vec<usize>]) -> Option<Vec<Vec<usize>>> {
+        let mut problem = Problem::new(OptimizationDirection::Maximize);
+        let counts: Vec<Vec<(usize, Variable)>> = candidates
+            .iter()
+            .map(|programs| {
+                let counts: Vec<(usize, Variable)> = programs
+                    .iter()
+                    .map(|&program| (program, problem.add_binary_var(0.0)))
+                    .collect();
+                let once: Vec<(Variable, f64)> =
+                    counts.iter().map(|&(_, var)| (var, 1.0)).collect();
+                problem.add_constraint(once, ComparisonOp::Le, 1.0);
+                counts
+            })
+            .collect();
+
+        let courses: Vec<Course> = self
+            .classes
+            .iter()
+            .map(|class| Course {
+                name: class.name(),
+                groups: class.groups().into_iter().map(str::to_owned).collect(),
+                credits: class.credits(),
+            })
+            .chain(self.taken.iter().map(|name| Course {
+                name: name.clone(),
+                groups: Vec::new(),
+                credits: 0,
+            }))
+            .collect();
+        for (index, program) in self.degree.programs.iter().enumerate() {
+            // courses as they count towards this program
+            let counted: Vec<(&Course, Variable)> = courses
+                .iter()
+                .zip(counts.iter())
+                .filter_map(|(course, counts)| {
+                    let &(_, var) = counts.iter().find(|(program, _)| *program == index)?;
+                    Some((course, var))
+                })
+                .collect();
+            // every program met counts the same
+            add_requirement(&mut problem, program, &counted, 1.0);
+        }
+
+        let solution = problem.solve().ok()?;
+        Some(
+            counts
+                .iter()
+                .zip(candidates)
+                .map(|(counts, programs)| {
+                    match counts.iter().find(|&&(_, var)| solution[var] > 0.5) {
+                        Some(&(program, _)) => vec![program],
+                        // it's not needed anywhere, so it may as well count somewhere it could
+                        None => programs.iter().take(1).copied().collect(),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Chooses a program for each course in `undecided`, which count towards all of their
+    /// `candidates` in `owners` until they're chosen.
+    fn assign(
+        &mut self,
+        owners: &mut [Vec<usize>],
+        candidates: &[Vec<usize>],
+        undecided: &[usize],
+    ) {
+        self.tried += 1;
+        let met = |progress: &[Progress]| -> Vec<bool> { progress.iter().map(|p| p.met).collect() };
+        let optimistic = self.progress(owners);
+        let possible = met(&optimistic);
+        let count = possible.iter().filter(|&&met| met).count();
+        if self.best.as_ref().is_some_and(|(best, _)| count <= *best) {
+            return;
+        }
+        let Some((&course, rest)) = undecided.split_first() else {
+            self.best = Some((count, optimistic));
+            return;
+        };
+
+        let mut pessimistic = owners.to_vec();
+        for &other in undecided {
+            pessimistic[other] = Vec::new();
+        }
+        let already = met(&self.progress(&pessimistic));
+
+        // programs which are met anyway or can't be met don't need the course, so it goes to
+        // the first one it could count towards without changing what's met
+        let mut useful: Vec<usize> = candidates[course]
+            .iter()
+            .copied()
+            .filter(|&program| possible[program] && !already[program])
+            .collect();
+        if useful.is_empty() {
+            useful.push(candidates[course][0]);
+        }
+        for program in useful {
+            owners[course] = vec![program];
+            self.assign(owners, candidates, rest);
+            if self.tried >= SHARING_LIMIT
+                || self
+                    .best
+                    .as_ref()
+                    .is_some_and(|(best, _)| *best == self.degree.programs.len())
+            {
+                break;
+            }
+        }
+        owners[course] = candidates[course].clone();
+    }
+}
+
+/// A course being shared out between programs by [`Sharing::exact`].
+struct Course {
+    name: String,
+    groups: Vec<String>,
+    credits: u8,
+}
+
+/// A variable which can only be 1 if the block is met by the `counted` courses, the way
+/// [`Requirement::audit`] checks it, worth `objective` when it is.
+fn add_requirement(
+    problem: &mut Problem,
+    requirement: &Requirement,
+    counted: &[(&Course, Variable)],
+    objective: f64,
+) -> Variable {
+    let met = problem.add_binary_var(objective);
+
+    for name in requirement.courses.iter() {
+        let mut done: Vec<(Variable, f64)> = counted
+            .iter()
+            .filter(|(course, _)| course.name == *name)
+            .map(|&(_, var)| (var, 1.0))
+            .collect();
+        done.push((met, -1.0));
+        problem.add_constraint(done, ComparisonOp::Ge, 0.0);
+    }
+
+    if requirement.count > 0 || requirement.credits > 0 {
+        let matching: Vec<(&Course, Variable)> = counted
+            .iter()
+            .filter(|(course, _)| {
+                requirement.from.contains(&course.name)
+                    || requirement
+                        .group
+                        .as_ref()
+                        .is_some_and(|group| course.groups.contains(group))
+            })
+            .copied()
+            .collect();
+        let mut courses: Vec<(Variable, f64)> =
+            matching.iter().map(|&(_, var)| (var, 1.0)).collect();
+        courses.push((met, -(requirement.count as f64)));
+        problem.add_constraint(courses, ComparisonOp::Ge, 0.0);
+        let mut credits: Vec<(Variable, f64)> = matching
+            .iter()
+            .map(|(course, var)| (*var, course.credits as f64))
+            .collect();
+        credits.push((met, -(requirement.credits as f64)));
+        problem.add_constraint(credits, ComparisonOp::Ge, 0.0);
+    }
+
+    for child in requirement.all.iter() {
+        let child = add_requirement(problem, child, counted, 0.0);
+        problem.add_constraint([(met, 1.0), (child, -1.0)], ComparisonOp::Le, 0.0);
+    }
+    if !requirement.any.is_empty() {
+        let mut any = vec![(met, 1.0)];
+        for child in requirement.any.iter() {
+            any.push((add_requirement(problem, child, counted, 0.0), -1.0));
+        }
+        problem.add_constraint(any, ComparisonOp::Le, 0.0);
+    }
+
+    met
+}
+
+impl Progress {
+    /// Names of the blocks directly inside this one which aren't met, or this block's own name if
+    /// it's unmet by itself.
+    pub fn unmet(&self) -> Vec<&str> {
+        let children: Vec<&str> = self
+            .children
+            .iter()
+            .filter(|child| !child.met)
+            .map(|child| child.name.as_str())
+            .collect();
+        match children.is_empty() && !self.met {
+            true => vec![self.name.as_str()],
+            false => children,
+        }
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let mark = if self.met { "✓".green() } else { "✗".red() };
+        write!(
+            f,
+            "{:indent$}{} {}",
+            "",
+            mark,
+            self.name.bold(),
+            indent = depth * 2
+        )?;
+        if let Some((courses, credits)) = self.counted {
+            let (count, needed_credits) = self.needed;
+            let mut parts = Vec::new();
+            if count > 0 {
+                parts.push(format!("{courses}/{count} courses"));
+            }
+            if needed_credits > 0 {
+                parts.push(format!("{credits}/{needed_credits} credits"));
+            }
+            write!(f, ": {}", parts.join(", "))?;
+        }
+        if !self.missing.is_empty() {
+            write!(f, " (missing {})", self.missing.join(", "))?;
+        }
+        writeln!(f)?;
+        for child in self.children.iter() {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{classes, config, root, taken};
+
+    fn degree(programs: &str) -> Degree {
+        toml::from_str(&format!("double_count = false\n{programs}")).unwrap()
+    }
+
+    fn met(progress: &[Progress]) -> Vec<bool> {
+        progress.iter().map(|program| program.met).collect()
+    }
+
+    #[test]
+    fn shared_courses() {
+        let degree = degree(
+            "[[programs]]
+             name = \"Major\"
+             courses = [\"ABC 100\"]
+             [[programs]]
+             name = \"Minor\"
+             from = [\"ABC 100\", \"ABC 101\"]
+             count = 1",
+        );
+        let classes = classes(
+            "ABC,100,3,false,,Fall,
+             ABC,101,3,false,,Fall,",
+        );
+        let progress = degree.audit(&classes, &taken(&[]));
+        assert_eq!(met(&progress), [true, true]);
+        assert_eq!(progress[1].counted, Some((1, 3)));
+
+        // with only one of them, it can only count towards one program
+        let progress = degree.audit(&classes[..1], &taken(&[]));
+        assert_eq!(met(&progress).iter().filter(|&&met| met).count(), 1);
+
+        // taken courses are shared the same way
+        let progress = degree.audit(&classes[1..], &taken(&["ABC 100"]));
+        assert_eq!(met(&progress), [true, true]);
+    }
+
+    /// Programs met when courses are shared out by the integer program alone.
+    fn met_exactly(degree: &Degree, classes: &[Arc<Class>], taken: &[String]) -> Vec<bool> {
+        let sharing = Sharing {
+            degree,
+            classes,
+            taken,
+            best: None,
+            tried: 0,
+        };
+        let owners = sharing.exact(&degree.candidates(classes, taken)).unwrap();
+        met(&sharing.progress(&owners))
+    }
+
+    #[test]
+    fn many_shared_courses() {
+        let rows: String = (100..140)
+            .map(|number| format!("ABC,{number},3,false,,Fall,\n"))
+            .collect();
+        let classes = classes(&rows);
+        let from: Vec<String> = classes
+            .iter()
+            .map(|class| format!("\"{}\"", class.name()))
+            .collect();
+        let programs = |count: u8| {
+            degree(
+                &["A", "B", "C"]
+                    .iter()
+                    .map(|name| {
+                        format!(
+                            "[[programs]]\nname = \"{name}\"\nfrom = [{}]\ncount = {count}\n",
+                            from.join(", ")
+                        )
+                    })
+                    .collect::<String>(),
+            )
+        };
+
+        assert_eq!(met(&programs(13).audit(&classes, &[])), [true, true, true]);
+        assert_eq!(
+            met_exactly(&programs(13), &classes, &[]),
+            [true, true, true]
+        );
+        // 40 courses can't make up three sets of 14, which the search gives up on proving
+        let progress = programs(14).audit(&classes, &[]);
+        assert_eq!(met(&progress).iter().filter(|&&met| met).count(), 2);
+        let exact = met_exactly(&programs(14), &classes, &[]);
+        assert_eq!(exact.iter().filter(|&&met| met).count(), 2);
+    }
+
+    #[test]
+    fn taken_classes_in_nested_blocks() {
+        let degree: Degree = toml::from_str(
+            "name = \"Degree\"
+             [[all]]
+             name = \"Core\"
+             courses = [\"ABC 101\"]
+             [[all]]
+             name = \"Electives\"
+             [[all.any]]
+             name = \"Group\"
+             group = \"GroupA\"
+             credits = 5
+             [[all.any]]
+             name = \"List\"
+             from = [\"XYZ 100\", \"XYZ 101\"]
+             count = 2",
+        )
+        .unwrap();
+        let classes = classes(
+            "ABC,100,3,false,GroupA,Fall,
+             ABC,101,3,false,,Fall,
+             ABC,102,2,false,GroupA,Fall,",
+        );
+        let config = config(1, 10, 1, "");
+        let audit = |names: &[&str]| root(&classes, &taken(names), &config).audit(&degree);
+
+        // taken classes in the catalog count for their groups and credits
+        let progress = audit(&["ABC 100", "ABC 101", "ABC 102"]);
+        assert!(progress[0].met);
+        let group = &progress[0].children[1].children[0];
+        assert_eq!((group.met, group.counted), (true, Some((2, 5))));
+
+        let progress = audit(&["ABC 100", "ABC 101"]);
+        assert!(!progress[0].met);
+        assert_eq!(progress[0].children[1].children[0].counted, Some((1, 3)));
+
+        // taken classes which aren't in the catalog still count by name
+        let progress = audit(&["ABC 101", "XYZ 100", "XYZ 101"]);
+        assert!(progress[0].met);
+        assert_eq!(progress[0].children[1].children[1].counted, Some((2, 0)));
+    }
+
+    #[test]
+    fn exact_sharing() {
+        let classes = classes(
+            "ABC,400,3,false,upper,Fall,
+             ABC,401,2,false,upper,Fall,
+             ABC,402,2,false,,Fall,
+             ABC,403,1,false,upper,Fall,",
+        );
+        for (major, minor) in [
+            (
+                "group = \"upper\"\ncredits = 5",
+                "from = [\"ABC 401\", \"ABC 402\"]\ncredits = 2",
+            ),
+            (
+                "group = \"upper\"\ncredits = 6",
+                "from = [\"ABC 400\", \"ABC 402\"]\ncount = 2",
+            ),
+            (
+                "courses = [\"ABC 400\"]\n[[programs.any]]\nname = \"Lab\"\ncourses = [\"ABC 403\"]",
+                "from = [\"ABC 400\", \"ABC 403\", \"XYZ 100\"]\ncount = 2",
+            ),
+        ] {
+            let degree = degree(&format!(
+                "[[programs]]\nname = \"Major\"\n{major}\n[[programs]]\nname = \"Minor\"\n{minor}"
+            ));
+            for taken in [&[][..], &["XYZ 100"]] {
+                let taken = crate::testing::taken(taken);
+                let searched = met(&degree.audit(&classes, &taken));
+                let exact = met_exactly(&degree, &classes, &taken);
+                assert_eq!(
+                    searched.iter().filter(|&&met| met).count(),
+                    exact.iter().filter(|&&met| met).count(),
+                    "{major} / {minor}"
+                );
+            }
+        }
+    }
+}
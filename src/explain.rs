@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::class::Class;
+use crate::config::Config;
+use crate::data::{Schedule, Semester, Term};
+use crate::requirements::RequisiteType;
+
+/// Why a class did or didn't end up in a term of a particular schedule.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Blocker {
+    Placed,
+    NotOffered,
+    TooManyCredits(u8),
+    /// Requisites which weren't met, even with every other offered class taken concurrently.
+    Requisites(Vec<String>),
+    /// Corequisites which would have to be taken in the same term, but never fit alongside it.
+    Corequisites(Vec<String>),
+    /// Classes bundled with it which aren't offered this term.
+    Bundle(Vec<String>),
+    /// Requisites were met, but no semester within the credit limits included it.
+    CreditLimits,
+}
+
+impl Display for Blocker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Blocker::Placed => write!(f, "Placed"),
+            Blocker::NotOffered => write!(f, "Not offered"),
+            Blocker::TooManyCredits(credits) => {
+                write!(f, "Worth {credits} credits, more than the maximum")
+            }
+            Blocker::Requisites(names) => write!(f, "Requisites un-met: {}", names.join(", ")),
+            Blocker::Corequisites(names) => {
+                write!(
+                    f,
+                    "Corequisites not taken concurrently: {}",
+                    names.join(", ")
+                )
+            }
+            Blocker::Bundle(names) => {
+                write!(f, "Bundled with classes not offered: {}", names.join(", "))
+            }
+            Blocker::CreditLimits => write!(f, "No semester within credit limits included it"),
+        }
+    }
+}
+
+pub struct TermReport {
+    pub term: Term,
+    /// Number of schedules which had already placed the class in an earlier term.
+    pub already_placed: usize,
+    pub blockers: Vec<(Blocker, usize)>,
+}
+
+/// Watches the search and records, for every term, why a class was or wasn't placed in each of
+/// the schedules a semester was added to.
+pub struct Explainer<'a> {
+    class: &'a Arc<Class>,
+    terms: BTreeMap<Term, (usize, Vec<Blocker>)>,
+}
+
+impl<'a> Explainer<'a> {
+    pub fn new(class: &'a Arc<Class>) -> Self {
+        Explainer {
+            class,
+            terms: BTreeMap::new(),
+        }
+    }
+
+    pub fn visit(&mut self, sched: &Schedule, children: &[Schedule], config: &Config) {
+        let (already_placed, blockers) = self.terms.entry(sched.next_term()).or_default();
+        if sched.remaining().contains(self.class) {
+            blockers.push(blocker(self.class, sched, children, config));
+        } else {
+            *already_placed += 1;
+        }
+    }
+
+    pub fn reports(self) -> Vec<TermReport> {
+        self.terms
+            .into_iter()
+            .map(|(term, (already_placed, blockers))| TermReport {
+                term,
+                already_placed,
+                blockers: blockers
+                    .into_iter()
+                    .counts()
+                    .into_iter()
+                    .sorted_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+fn blocker(
+    class: &Arc<Class>,
+    sched: &Schedule,
+    children: &[Schedule],
+    config: &Config,
+) -> Blocker {
+    let term = sched.next_term();
+    if !class.offered(&term) {
+        return Blocker::NotOffered;
+    }
+    if class.credits() > config.max_credits {
+        return Blocker::TooManyCredits(class.credits());
+    }
+    if children
+        .iter()
+        .any(|child| !child.remaining().contains(class))
+    {
+        return Blocker::Placed;
+    }
+    if let Some(bundle) = class.bundle() {
+        let unoffered: Vec<String> = sched
+            .remaining()
+            .iter()
+            .filter(|other| other.bundle() == Some(bundle) && !other.offered(&term))
+            .map(|other| other.name())
+            .collect();
+        if !unoffered.is_empty() {
+            return Blocker::Bundle(unoffered);
+        }
+    }
+
+    // prerequisites only look at earlier terms, so taking everything offered this term at once
+    // leaves only the requisites which no choice of classes this term could meet
+    let offered: Vec<Arc<Class>> = sched
+        .remaining()
+        .iter()
+        .filter(|other| other.offered(&term))
+        .cloned()
+        .collect();
+    let together = sched.with_semester(Arc::new(Semester::new(offered, term)));
+    let unmet = class.unmet_requisites(&together);
+    if !unmet.is_empty() {
+        return Blocker::Requisites(unmet);
+    }
+
+    let alone = sched.with_semester(Arc::new(Semester::new(vec![class.clone()], term)));
+    let unmet: Vec<String> = class
+        .parsed_requisites()
+        .unwrap_or_default()
+        .unmet(&alone)
+        .into_iter()
+        .filter(|req| req.typ != RequisiteType::Pre)
+        .map(|req| req.name.clone())
+        .unique()
+        .collect();
+    if !unmet.is_empty() {
+        return Blocker::Corequisites(unmet);
+    }
+
+    // lecture/lab pairs go in the same term, so one can't be taken without the other
+    let linking: Vec<String> = sched
+        .remaining()
+        .iter()
+        .filter(|other| other.linked().contains(&class.name()))
+        .map(|other| other.name())
+        .collect();
+    if !linking.is_empty() {
+        return Blocker::Corequisites(linking);
+    }
+
+    Blocker::CreditLimits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{classes, config, root};
+
+    /// Why each class was or wasn't placed in the first term.
+    fn blockers(name: &str) -> Vec<(Blocker, usize)> {
+        let classes = classes(
+            "ABC,100,3,false,,Fall,
+             ABC,101,3,false,,Fall|Spring,ABC 100
+             ABC,102,3,false,,Spring,
+             ABC,103,3,false,,Fall,
+             ABC,104,4,false,,Fall,
+             ABC,105,9,false,,Fall,",
+        );
+        let config = config(6, 6, 1, "");
+        let class = classes.iter().find(|class| class.name() == name).unwrap();
+        let mut explainer = Explainer::new(class);
+        crate::search_with(root(&classes, &[], &config), &config, |sched, children| {
+            explainer.visit(sched, children, &config)
+        });
+        let reports = explainer.reports();
+        assert_eq!(reports.len(), 1);
+        reports[0].blockers.clone()
+    }
+
+    #[test]
+    fn placed() {
+        assert_eq!(blockers("ABC 100"), [(Blocker::Placed, 1)]);
+    }
+
+    #[test]
+    fn blocked_by_requisite() {
+        assert_eq!(
+            blockers("ABC 101"),
+            [(Blocker::Requisites(vec!["ABC 100".to_owned()]), 1)]
+        );
+    }
+
+    #[test]
+    fn not_offered() {
+        assert_eq!(blockers("ABC 102"), [(Blocker::NotOffered, 1)]);
+    }
+
+    #[test]
+    fn credit_limits() {
+        // 4 credits never adds up to exactly 6 with the 3 credit classes
+        assert_eq!(blockers("ABC 104"), [(Blocker::CreditLimits, 1)]);
+        assert_eq!(blockers("ABC 105"), [(Blocker::TooManyCredits(9), 1)]);
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::class::{self, Class};
+use crate::data::Term;
+use crate::requirements::{
+    EvalExpression, Expression, RequisiteName, RequisiteType, TestRequisite,
+};
+
+/// Requisite dependencies between the classes in the catalog, along with the earliest term each
+/// class could possibly be taken in. Credit limits are ignored, so the earliest term is a lower
+/// bound rather than a promise.
+pub struct RequisiteGraph {
+    classes: Vec<(Arc<Class>, Expression)>,
+    /// Classes whose requisites can't be parsed, which are never placed.
+    invalid: Vec<(String, String)>,
+    taken: HashSet<String>,
+    terms: Vec<Term>,
+    earliest: HashMap<String, usize>,
+}
+
+/// Answers requisite queries as if every class were taken in its earliest term so far.
+struct Placement<'a> {
+    taken: &'a HashSet<String>,
+    earliest: &'a HashMap<String, usize>,
+    term: usize,
+    offered: &'a HashSet<String>,
+}
+
+impl TestRequisite for Placement<'_> {
+    fn has_prerequisite(&self, requisite: &RequisiteName) -> bool {
+        self.taken.contains(requisite)
+            || self.earliest.get(requisite).is_some_and(|&t| t < self.term)
+    }
+
+    fn has_corequisite(&self, requisite: &RequisiteName) -> bool {
+        self.taken.contains(requisite)
+            || self
+                .earliest
+                .get(requisite)
+                .is_some_and(|&t| t <= self.term)
+    }
+
+    fn has_concurrent(&self, requisite: &RequisiteName) -> bool {
+        self.taken.contains(requisite)
+            || self.offered.contains(requisite)
+                && self
+                    .earliest
+                    .get(requisite)
+                    .is_some_and(|&t| t <= self.term)
+    }
+}
+
+/// Offerings repeat at most every two years, so once a class hasn't been placed for two years
+/// nothing else ever will be.
+const TERMS_PER_CYCLE: usize = 6;
+
+impl RequisiteGraph {
+    pub fn new(classes: &[Arc<Class>], taken: &[String], first_term: Term) -> Self {
+        let mut parsed = Vec::new();
+        let mut invalid = Vec::new();
+        for class in classes {
+            match class.parsed_requisites() {
+                Ok(expression) => parsed.push((class.clone(), expression)),
+                Err(err) => invalid.push((class.name(), err)),
+            }
+        }
+
+        let mut graph = RequisiteGraph {
+            classes: parsed,
+            invalid,
+            taken: taken.iter().cloned().collect(),
+            terms: vec![first_term],
+            earliest: HashMap::new(),
+        };
+        graph.place();
+        graph
+    }
+
+    fn place(&mut self) {
+        // classes in a bundle are only taken together, so they're placed together
+        let classes: Vec<Arc<Class>> = self
+            .classes
+            .iter()
+            .map(|(class, _)| class.clone())
+            .collect();
+        let mut unit_of: Vec<usize> = vec![0; classes.len()];
+        let units = class::units(&classes);
+        for (unit, members) in units.iter().enumerate() {
+            for &i in members {
+                unit_of[i] = unit;
+            }
+        }
+
+        let mut unplaced: Vec<usize> = (0..self.classes.len()).collect();
+        let mut last_placed = 0;
+        let mut index = 0;
+        while !unplaced.is_empty() && index - last_placed < TERMS_PER_CYCLE {
+            let term = self.terms[index];
+            let offered: HashSet<String> = self
+                .classes
+                .iter()
+                .filter(|(class, _)| class.offered(&term))
+                .map(|(class, _)| class.name())
+                .collect();
+
+            // start by assuming everything offered can be taken this term and drop classes until
+            // nothing changes, since corequisites can be taken together in the same term
+            let mut placed: Vec<usize> = unplaced
+                .iter()
+                .copied()
+                .filter(|&i| offered.contains(&self.classes[i].0.name()))
+                .collect();
+            for &i in placed.iter() {
+                self.earliest.insert(self.classes[i].0.name(), index);
+            }
+            loop {
+                let placement = Placement {
+                    taken: &self.taken,
+                    earliest: &self.earliest,
+                    term: index,
+                    offered: &offered,
+                };
+                let this_term: HashSet<usize> = placed.iter().copied().collect();
+                let (kept, dropped): (Vec<usize>, Vec<usize>) = placed.iter().partition(|&&i| {
+                    self.classes[i].1.eval(&placement)
+                        && units[unit_of[i]]
+                            .iter()
+                            .all(|other| this_term.contains(other))
+                });
+                if dropped.is_empty() {
+                    break;
+                }
+                for i in dropped {
+                    self.earliest.remove(&self.classes[i].0.name());
+                }
+                placed = kept;
+            }
+
+            if !placed.is_empty() {
+                last_placed = index;
+                let placed: HashSet<usize> = placed.into_iter().collect();
+                unplaced.retain(|i| !placed.contains(i));
+            }
+            index += 1;
+            self.terms.push(term.next());
+        }
+    }
+
+    /// The earliest term a class could be taken in, or `None` if it can never be taken.
+    pub fn earliest(&self, class: &Class) -> Option<Term> {
+        self.earliest_index(class).map(|index| self.terms[index])
+    }
+
+    /// Like [`RequisiteGraph::earliest`], but counted in terms after the first term.
+    pub fn earliest_index(&self, class: &Class) -> Option<usize> {
+        self.earliest.get(&class.name()).copied()
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &Arc<Class>> {
+        self.classes.iter().map(|(class, _)| class)
+    }
+
+    /// Classes whose requisites couldn't be parsed, with the parse error.
+    pub fn invalid(&self) -> &[(String, String)] {
+        &self.invalid
+    }
+
+    /// Required classes which can never be taken, or can't be taken within `semesters` terms.
+    pub fn unsatisfiable(&self, semesters: u8) -> Vec<(Arc<Class>, Option<Term>)> {
+        self.classes()
+            .filter(|class| class.required)
+            .filter(|class| match self.earliest_index(class) {
+                Some(index) => index >= semesters.into(),
+                None => true,
+            })
+            .map(|class| (class.clone(), self.earliest(class)))
+            .collect()
+    }
+
+    /// Requisite cycles between classes which can never be taken. Cycles made only of
+    /// "or concurrently" requisites are fine since the classes can share a semester, and cycles
+    /// broken by an alternative in an "or" never leave a class unplaced.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut edges: BTreeMap<String, Vec<(String, RequisiteType)>> = BTreeMap::new();
+        for (class, expression) in self.classes.iter() {
+            if self.earliest_index(class).is_some() {
+                continue;
+            }
+            let deps = expression
+                .requisites()
+                .into_iter()
+                .filter(|req| !self.taken.contains(&req.name))
+                .map(|req| (req.name.clone(), req.typ))
+                .collect();
+            edges.insert(class.name(), deps);
+        }
+
+        let mut cycles = Vec::new();
+        let mut done = BTreeSet::new();
+        for start in edges.keys() {
+            let mut path = Vec::new();
+            visit(start, &edges, &mut path, &mut done, &mut cycles);
+        }
+        cycles
+    }
+}
+
+fn visit<'a>(
+    name: &'a String,
+    edges: &'a BTreeMap<String, Vec<(String, RequisiteType)>>,
+    path: &mut Vec<(&'a String, RequisiteType)>,
+    done: &mut BTreeSet<&'a String>,
+    cycles: &mut Vec<Vec<String>>,
+) {
+    if done.contains(name) {
+        return;
+    }
+    if let Some(pos) = path.iter().position(|(seen, _)| *seen == name) {
+        let cycle = &path[pos..];
+        if cycle.iter().any(|(_, typ)| *typ == RequisiteType::Pre) {
+            let mut names: Vec<String> = cycle.iter().map(|(n, _)| (*n).clone()).collect();
+            names.push(name.clone());
+            cycles.push(names);
+        }
+        return;
+    }
+    let Some(deps) = edges.get(name) else {
+        return;
+    };
+    for (dep, typ) in deps {
+        path.push((name, *typ));
+        visit(dep, edges, path, done, cycles);
+        path.pop();
+    }
+    done.insert(name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::TermSeason;
+    use crate::testing::{classes, taken};
+
+    fn earliest(graph: &RequisiteGraph) -> Vec<(String, Option<Term>)> {
+        graph
+            .classes()
+            .map(|class| (class.name(), graph.earliest(class)))
+            .collect()
+    }
+
+    #[test]
+    fn earliest_terms() {
+        let fall = |year| Some(Term::new(TermSeason::Fall, year));
+        let spring = |year| Some(Term::new(TermSeason::Spring, year));
+        let classes = classes(
+            "ABC,100,3,false,,Fall|Spring,
+             ABC,200,3,false,,Spring,ABC 100
+             ABC,201,3,false,,Fall,ABC 200 and ABC 099
+             ABC,202,1,false,,Spring,ABC 200 or concurrently
+             ABC,203,1,false,,Fall,ABC 204 or concurrently
+             ABC,204,3,false,,FallEven,
+             ABC,300,3,false,,Summer,ABC 999",
+        );
+        let graph = RequisiteGraph::new(
+            &classes,
+            &taken(&["ABC 099"]),
+            Term::new(TermSeason::Fall, 2024),
+        );
+        assert_eq!(
+            earliest(&graph),
+            [
+                ("ABC 100".to_owned(), fall(2024)),
+                ("ABC 200".to_owned(), spring(2025)),
+                ("ABC 201".to_owned(), fall(2025)),
+                ("ABC 202".to_owned(), spring(2025)),
+                ("ABC 203".to_owned(), fall(2024)),
+                ("ABC 204".to_owned(), fall(2024)),
+                ("ABC 300".to_owned(), None),
+            ]
+        );
+        assert!(graph.cycles().is_empty());
+
+        // odd years are skipped until the next even fall
+        let graph = RequisiteGraph::new(&classes, &[], Term::new(TermSeason::Fall, 2025));
+        assert_eq!(earliest(&graph)[5], ("ABC 204".to_owned(), fall(2026)));
+    }
+
+    #[test]
+    fn cycles() {
+        let classes = classes(
+            "ABC,100,3,false,,Fall|Spring,ABC 101
+             ABC,101,3,false,,Fall|Spring,ABC 102
+             ABC,102,3,false,,Fall|Spring,ABC 100
+             ABC,200,3,false,,Fall|Spring,ABC 201 or concurrently
+             ABC,201,3,false,,Fall|Spring,ABC 200 or concurrently
+             ABC,300,3,false,,Fall|Spring,ABC 301 or ABC 400
+             ABC,301,3,false,,Fall|Spring,ABC 300
+             ABC,400,3,false,,Fall|Spring,",
+        );
+        let graph = RequisiteGraph::new(&classes, &[], Term::new(TermSeason::Fall, 2024));
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            graph.cycles(),
+            [names(&["ABC 100", "ABC 101", "ABC 102", "ABC 100"])]
+        );
+        // the concurrent cycle and the cycle with a way out are both placed
+        assert!(graph
+            .classes()
+            .filter(|class| class.name().as_str() >= "ABC 200")
+            .all(|class| graph.earliest(class).is_some()));
+    }
+
+    #[test]
+    fn bundles() {
+        // the lab is only offered in the spring, so the lecture has to wait for it
+        let classes = classes(
+            "PHY,183,4,true,,Fall|Spring,,physics
+             PHY,191,1,true,,Spring,,physics
+             PHY,184,4,false,,Fall|Spring,PHY 183
+             CHM,141,4,false,,Fall,,chemistry
+             CHM,161,1,false,,Spring,,chemistry",
+        );
+        let graph = RequisiteGraph::new(&classes, &[], Term::new(TermSeason::Fall, 2024));
+        let spring = Some(Term::new(TermSeason::Spring, 2025));
+        let fall = Some(Term::new(TermSeason::Fall, 2025));
+        assert_eq!(
+            earliest(&graph),
+            [
+                ("PHY 183".to_owned(), spring),
+                ("PHY 191".to_owned(), spring),
+                ("PHY 184".to_owned(), fall),
+                ("CHM 141".to_owned(), None),
+                ("CHM 161".to_owned(), None),
+            ]
+        );
+        assert_eq!(graph.unsatisfiable(1).len(), 2);
+    }
+
+    #[test]
+    fn unparsable_requisites() {
+        let classes = classes(
+            "ABC,100,3,true,,Fall,ABC 099 and and
+             ABC,200,3,false,,Fall,ABC 100",
+        );
+        let graph = RequisiteGraph::new(&classes, &[], Term::new(TermSeason::Fall, 2024));
+        assert_eq!(graph.invalid().len(), 1);
+        assert_eq!(graph.invalid()[0].0, "ABC 100");
+        assert_eq!(earliest(&graph), [("ABC 200".to_owned(), None)]);
+    }
+
+    #[test]
+    fn long_chains() {
+        // each class needs the one before it, so placing them takes a term each
+        let rows: String = (0..300)
+            .map(|n| match n {
+                0 => "ABC,1000,1,false,,Fall|Spring|Summer,\n".to_owned(),
+                n => format!(
+                    "ABC,{},1,false,,Fall|Spring|Summer,ABC {}\n",
+                    1000 + n,
+                    999 + n
+                ),
+            })
+            .collect();
+        let classes = classes(&rows);
+        let graph = RequisiteGraph::new(&classes, &[], Term::new(TermSeason::Fall, 2024));
+        assert_eq!(graph.earliest_index(&classes[299]), Some(299));
+        // terms stop being added once everything is placed
+        assert_eq!(graph.terms.len(), 301);
+    }
+}
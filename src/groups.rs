@@ -0,0 +1,257 @@
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::class::Class;
+use crate::config::{Config, Group};
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    credits: u16,
+    courses: u16,
+}
+
+/// Whether the classes can be counted towards groups so that every group gets its credits, and
+/// its number of courses if `count_courses` is set. A class counts towards every group it's in,
+/// except that it only counts towards one group of each exclusive set, and a class which would
+/// put a group over its maximum doesn't count towards that group. Classes count in whole, never
+/// for part of their credits.
+///
+/// When the groups aren't met, the error names the groups which can't be met even on their own,
+/// or every group with a requirement if they can each be met but not all at once.
+pub fn groups_met(
+    classes: &[Arc<Class>],
+    config: &Config,
+    count_courses: bool,
+) -> Result<(), Vec<String>> {
+    let names: Vec<&String> = config.groups.keys().collect();
+    let limits: Vec<Group> = config.groups.values().cloned().collect();
+    let index = |group: &str| {
+        names
+            .iter()
+            .position(|name| *name == group)
+            .expect("Class has a group which is not present in config")
+    };
+    let exclusive: Vec<Vec<usize>> = config
+        .exclusive
+        .iter()
+        .map(|set| {
+            set.iter()
+                .filter_map(|group| names.iter().position(|name| *name == group))
+                .collect()
+        })
+        .collect();
+
+    let choices: Vec<(u16, Vec<Vec<usize>>)> = classes
+        .iter()
+        .filter(|class| !class.groups().is_empty())
+        .map(|class| {
+            let groups: Vec<usize> = class.groups().into_iter().map(index).unique().collect();
+            let capped: Vec<usize> = groups
+                .iter()
+                .copied()
+                .filter(|&g| limits[g].max_credits.is_some())
+                .collect();
+            (
+                class.credits() as u16,
+                options(&groups, &exclusive, &capped),
+            )
+        })
+        .collect();
+
+    let met = |limits: &[Group]| {
+        let mut tally = vec![Tally::default(); names.len()];
+        search(&choices, &mut tally, limits, count_courses)
+    };
+    if met(&limits) {
+        return Ok(());
+    }
+
+    // only the group being checked needs its credits and courses, but maximums still apply
+    let required: Vec<usize> = (0..names.len())
+        .filter(|&g| limits[g].credits > 0 || (count_courses && limits[g].courses > 0))
+        .collect();
+    let alone: Vec<usize> = required
+        .iter()
+        .copied()
+        .filter(|&g| {
+            let relaxed: Vec<Group> = limits
+                .iter()
+                .enumerate()
+                .map(|(other, limit)| match other == g {
+                    true => limit.clone(),
+                    false => Group {
+                        max_credits: limit.max_credits,
+                        ..Group::default()
+                    },
+                })
+                .collect();
+            !met(&relaxed)
+        })
+        .collect();
+    let unmet = if alone.is_empty() { required } else { alone };
+    Err(unmet.into_iter().map(|g| names[g].clone()).collect())
+}
+
+/// The sets of groups a class in `groups` could count towards.
+fn options(groups: &[usize], exclusive: &[Vec<usize>], capped: &[usize]) -> Vec<Vec<usize>> {
+    let valid: Vec<Vec<usize>> = groups
+        .iter()
+        .copied()
+        .powerset()
+        .filter(|option| {
+            exclusive
+                .iter()
+                .all(|set| option.iter().filter(|g| set.contains(g)).count() <= 1)
+        })
+        .collect();
+
+    // counting towards fewer groups never helps, unless it keeps a group under its maximum, so
+    // only capped groups are ever left out
+    valid
+        .iter()
+        .filter(|option| {
+            !valid
+                .iter()
+                .any(|other| other.len() > option.len() && option.iter().all(|g| other.contains(g)))
+        })
+        .flat_map(|option| {
+            let (always, optional): (Vec<usize>, Vec<usize>) =
+                option.iter().partition(|g| !capped.contains(g));
+            optional.into_iter().powerset().map(move |counted| {
+                let mut option = always.clone();
+                option.extend(counted);
+                option.sort_unstable();
+                option
+            })
+        })
+        .unique()
+        .collect()
+}
+
+fn search(
+    choices: &[(u16, Vec<Vec<usize>>)],
+    tally: &mut [Tally],
+    limits: &[Group],
+    count_courses: bool,
+) -> bool {
+    // give up early once the classes left can't make up a group's credits or courses
+    let mut potential = tally.to_vec();
+    for (credits, options) in choices {
+        for &g in options.iter().flatten().unique() {
+            potential[g].credits += credits;
+            potential[g].courses += 1;
+        }
+    }
+    if potential.iter().zip(limits).any(|(potential, limit)| {
+        potential.credits < limit.credits.into()
+            || (count_courses && potential.courses < limit.courses.into())
+    }) {
+        return false;
+    }
+
+    let Some(((credits, options), rest)) = choices.split_first() else {
+        return true;
+    };
+    for option in options {
+        if option.iter().any(|&g| {
+            limits[g]
+                .max_credits
+                .is_some_and(|max| tally[g].credits + credits > max.into())
+        }) {
+            continue;
+        }
+
+        option.iter().for_each(|&g| {
+            tally[g].credits += credits;
+            tally[g].courses += 1;
+        });
+        let met = search(rest, tally, limits, count_courses);
+        option.iter().for_each(|&g| {
+            tally[g].credits -= credits;
+            tally[g].courses -= 1;
+        });
+        if met {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{classes, config};
+
+    fn met(rows: &str, groups: &str) -> bool {
+        groups_met(&classes(rows), &config(1, 10, 2, groups), true).is_ok()
+    }
+
+    #[test]
+    fn caps() {
+        let groups = "groups = { core = 6, upper = { max_credits = 3 } }";
+        // the second class still counts towards core once upper is full
+        assert!(met(
+            "ABC,400,3,false,core|upper,Fall,
+             ABC,401,3,false,core|upper,Fall,",
+            groups
+        ));
+        assert!(!met("ABC,400,3,false,core|upper,Fall,", groups));
+
+        // a class over the maximum doesn't count towards the capped group
+        let groups = "groups = { upper = { credits = 3, max_credits = 3 } }";
+        assert!(!met("ABC,400,4,false,upper,Fall,", groups));
+        assert!(met(
+            "ABC,400,4,false,upper,Fall,
+             ABC,401,3,false,upper,Fall,",
+            groups
+        ));
+    }
+
+    #[test]
+    fn exclusivity() {
+        let groups = "groups = { core = 3, focus = 3 }\nexclusive = [[\"core\", \"focus\"]]";
+        assert!(!met("ABC,100,3,false,core|focus,Fall,", groups));
+        assert!(met(
+            "ABC,100,3,false,core|focus,Fall,
+             ABC,101,3,false,core|focus,Fall,",
+            groups
+        ));
+
+        // without the exclusive set, one class counts towards both
+        assert!(met(
+            "ABC,100,3,false,core|focus,Fall,",
+            "groups = { core = 3, focus = 3 }"
+        ));
+    }
+
+    #[test]
+    fn caps_with_exclusivity() {
+        let groups = "groups = { core = 6, focus = 3, upper = { max_credits = 3 } }\n\
+                      exclusive = [[\"core\", \"focus\"]]";
+        assert!(met(
+            "ABC,400,3,false,core|upper,Fall,
+             ABC,401,3,false,core|focus|upper,Fall,
+             ABC,402,3,false,core|focus|upper,Fall,",
+            groups
+        ));
+        assert!(!met(
+            "ABC,400,3,false,core|upper,Fall,
+             ABC,401,3,false,core|focus|upper,Fall,",
+            groups
+        ));
+    }
+
+    #[test]
+    fn unmet_groups() {
+        let groups = "groups = { core = 3, focus = 3, extra = 0 }
+exclusive = [[\"core\", \"focus\"]]";
+        let config = config(1, 10, 2, groups);
+        let unmet = |rows| groups_met(&classes(rows), &config, false).unwrap_err();
+
+        // focus has no classes at all
+        assert_eq!(unmet("ABC,100,3,false,core,Fall,"), ["focus"]);
+        // each group could be met, but not both with one class
+        assert_eq!(unmet("ABC,100,3,false,core|focus,Fall,"), ["core", "focus"]);
+    }
+}
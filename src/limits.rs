@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+use yansi::Paint;
+
+use crate::config::Config;
+
+/// How often the progress line is printed.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitReached {
+    #[error("expanded {0} schedules (max_nodes)")]
+    Nodes(usize),
+    #[error("ran for {0} seconds (time_limit)")]
+    Time(u64),
+    #[error("had {0} schedules to consider at once (max_schedules)")]
+    Schedules(usize),
+}
+
+/// Keeps track of how much searching has been done, so it can be stopped before it runs for too
+/// long or holds too many schedules.
+pub struct Limits {
+    max_nodes: Option<usize>,
+    time_limit: Option<Duration>,
+    max_schedules: Option<usize>,
+    progress: bool,
+    started: Instant,
+    last_progress: Instant,
+    /// Schedules which have had a semester added so far.
+    pub nodes: usize,
+}
+
+impl Limits {
+    pub fn new(config: &Config) -> Self {
+        let now = Instant::now();
+        Limits {
+            max_nodes: config.max_nodes,
+            time_limit: config.time_limit.map(Duration::from_secs),
+            max_schedules: config.max_schedules,
+            progress: config.progress,
+            started: now,
+            last_progress: now,
+            nodes: 0,
+        }
+    }
+
+    /// Whether the search has to stop, with `schedules` being held in memory.
+    pub fn check(&self, schedules: usize) -> Result<(), LimitReached> {
+        if let Some(max) = self.max_nodes.filter(|&max| self.nodes >= max) {
+            return Err(LimitReached::Nodes(max));
+        }
+        if let Some(limit) = self
+            .time_limit
+            .filter(|&limit| self.started.elapsed() >= limit)
+        {
+            return Err(LimitReached::Time(limit.as_secs()));
+        }
+        if let Some(max) = self.max_schedules.filter(|&max| schedules > max) {
+            return Err(LimitReached::Schedules(max));
+        }
+        Ok(())
+    }
+
+    /// Schedules which can still be expanded before `max_nodes` is reached, if it's set.
+    pub fn nodes_left(&self) -> Option<usize> {
+        self.max_nodes.map(|max| max.saturating_sub(self.nodes))
+    }
+
+    /// Prints a progress line if it's been a while since the last one.
+    pub fn report(&mut self, term: u8, terms: u8, schedules: usize, errors: usize) {
+        if !self.progress || self.last_progress.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        self.last_progress = Instant::now();
+        eprintln!(
+            "{}",
+            format!(
+                "Term {term}/{terms}: {schedules} schedules, {} expanded, {errors} errors ({}s)",
+                self.nodes,
+                self.started.elapsed().as_secs()
+            )
+            .dim()
+        );
+    }
+}
@@ -1,39 +1,82 @@
-#![feature(iterator_try_collect)]
-#![feature(iter_collect_into)]
-#![feature(get_many_mut)]
 mod class;
+mod collapse;
 mod config;
 mod data;
+mod degree;
+mod explain;
+mod graph;
+mod groups;
+mod limits;
+mod plan;
 mod requirements;
+mod sample;
+mod solve;
+mod stream;
+#[cfg(test)]
+mod testing;
+mod validate;
 
-use std::{fs, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fmt::Display,
+    fs, io, process,
+    sync::Arc,
+};
 
 use config::Config;
 use csv::ReaderBuilder;
 use yansi::Paint;
 
 use class::Class;
-use data::{Schedule, ScheduleError};
+use data::{ErrorCounts, Schedule, ScheduleError, ScheduleState};
+use explain::{Blocker, Explainer};
+use graph::RequisiteGraph;
 use itertools::Itertools;
+use limits::{LimitReached, Limits};
+use plan::Plan;
+use rayon::prelude::*;
+use requirements::Primary;
+use stream::Stream;
+use validate::ValidationError;
 
-fn step_schedules<'a>(
+/// Schedules each thread adds a semester to between checking search limits.
+const CHUNK_PER_THREAD: usize = 8;
+
+/// Adds a semester to every schedule, spreading the schedules across threads. Each schedule comes
+/// out with its children, in the same order as a single-threaded search would produce them.
+fn step_schedules(
     input: Vec<Schedule>,
     config: &Config,
-) -> Vec<Result<Schedule, ScheduleError>> {
+) -> Vec<(Schedule, Vec<Result<Schedule, ScheduleError>>)> {
     input
-        .into_iter()
+        .into_par_iter()
         .map(|sched| {
-            sched
+            let children = sched
                 .generate_possible(config)
                 .into_iter()
                 .map(|sem_result| match sem_result {
                     Ok(sem) => sched.child(sem),
                     Err(err) => Err(err),
                 })
-                .collect::<Vec<Result<Schedule, ScheduleError>>>()
+                .collect::<Vec<Result<Schedule, ScheduleError>>>();
+            (sched, children)
         })
-        .flatten()
-        .collect::<Vec<Result<Schedule, ScheduleError>>>()
+        .collect()
+}
+
+/// Drops schedules in the same state as one seen before, so each state is only expanded once.
+fn merge_duplicates(
+    scheds: Vec<Schedule>,
+    seen: &mut HashSet<ScheduleState>,
+) -> (Vec<Schedule>, usize) {
+    let before = scheds.len();
+    let merged: Vec<Schedule> = scheds
+        .into_iter()
+        .filter(|sched| seen.insert(sched.state()))
+        .collect();
+    let duplicates = before - merged.len();
+    (merged, duplicates)
 }
 
 fn split_result_vec<T, E>(results: Vec<Result<T, E>>) -> (Vec<T>, Vec<E>) {
@@ -50,12 +93,7 @@ fn split_result_vec<T, E>(results: Vec<Result<T, E>>) -> (Vec<T>, Vec<E>) {
     (successes, errors)
 }
 
-fn main() {
-    let mut rdr = ReaderBuilder::new()
-        .comment(Some(b'#'))
-        .from_path("input.csv")
-        .unwrap();
-
+fn load_inputs() -> (Vec<Arc<Class>>, Vec<String>, Config) {
     let classes_taken: Vec<String> = fs::read_to_string("taken.txt")
         .unwrap()
         .lines()
@@ -64,48 +102,717 @@ fn main() {
         .collect();
 
     let config_str = fs::read_to_string("config.toml").unwrap();
-    let config: Config = toml::from_str(&config_str).unwrap();
+    let mut config: Config = toml::from_str(&config_str).unwrap();
+    if let Ok(degree_str) = fs::read_to_string("degree.toml") {
+        config.degree = Some(toml::from_str(&degree_str).unwrap());
+    }
+
+    let classes = read_classes(fs::File::open("input.csv").unwrap());
 
-    let classes: Vec<Rc<Class>> = rdr
+    (classes, classes_taken, config)
+}
+
+fn read_classes(input: impl io::Read) -> Vec<Arc<Class>> {
+    ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .from_reader(input)
         .deserialize()
-        .map(|x| Rc::new(x.unwrap()))
-        .collect::<Vec<Rc<Class>>>();
+        .map(|x| {
+            let mut class: Class = x.unwrap();
+            class.cache_requisites();
+            Arc::new(class)
+        })
+        .collect::<Vec<Arc<Class>>>()
+}
+
+fn validate_inputs(classes: &[Arc<Class>], classes_taken: Vec<String>, config: &Config) {
+    let root = starting_schedule(classes, classes_taken, config);
+    let taken = planned_taken(&root);
+    let graph = RequisiteGraph::new(classes, &taken, root.next_term());
+
+    println!("{}", "Earliest possible terms".bold().blue());
+    for class in graph.classes() {
+        match graph.earliest(class) {
+            Some(term) => println!("{}: {}", class.name(), term),
+            None => println!("{}: {}", class.name(), "never".red()),
+        }
+    }
+    println!();
+
+    let errors = validate::validate(classes, &taken, config, &graph);
+    if errors.is_empty() {
+        println!("{}", "No problems found".green().bold());
+        return;
+    }
+
+    println!("{}", "Problems".bold().bright_red());
+    for error in errors.iter() {
+        println!("{error}");
+    }
+    process::exit(1);
+}
+
+fn explain_class(name: &str, classes: &[Arc<Class>], classes_taken: Vec<String>, config: &Config) {
+    let Some(class) = classes.iter().find(|class| class.name() == name) else {
+        eprintln!("{name} is not in input.csv");
+        process::exit(1);
+    };
+
+    let root = starting_schedule(classes, classes_taken, config);
+    print_warnings(classes, &root, config);
+    let mut explainer = Explainer::new(class);
+    let search = search_with(root, config, |sched, children| {
+        explainer.visit(sched, children, config)
+    });
+    for report in explainer.reports() {
+        println!("{}", report.term.bold().blue());
+        for (blocker, count) in report.blockers.iter() {
+            match blocker {
+                Blocker::Placed => println!("{count}: {}", blocker.green()),
+                _ => println!("{count}: {blocker}"),
+            }
+        }
+        if report.already_placed > 0 {
+            println!(
+                "{}",
+                format!("{}: Already placed", report.already_placed).dim()
+            );
+        }
+        println!();
+    }
+    print_stopped(search.stopped, config, "later terms weren't explained");
+}
+
+fn show_requisites(
+    name: &str,
+    classes: &[Arc<Class>],
+    classes_taken: Vec<String>,
+    config: &Config,
+) {
+    let Some(class) = classes.iter().find(|class| class.name() == name) else {
+        eprintln!("{name} is not in input.csv");
+        process::exit(1);
+    };
+
+    let parsed = match class.parsed_requisites() {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            let problem = ValidationError::InvalidRequisites(class.name(), err);
+            eprintln!("{} {}", "Error:".red().bold(), problem);
+            process::exit(1);
+        }
+    };
+    if parsed.requisites().is_empty() {
+        println!("{} has no requisites", class.name());
+        return;
+    }
+
+    let simplified = parsed.normalize();
+    println!("{} requires {}", class.name().bold(), simplified);
+    println!();
+
+    let options = simplified.to_dnf();
+    println!("{}", "Ways to meet requisites:".bold());
+    for option in options.0 .0.iter().flat_map(|or| or.0.iter()) {
+        match option {
+            Primary::Req(requisite) => println!("{requisite}"),
+            Primary::Expr(expression) => println!("{expression}"),
+        }
+    }
+    println!();
 
-    let mut scheds: Vec<Schedule> = vec![Schedule::new(
-        &classes,
-        Rc::new(classes_taken),
-        config.starting_term,
-    )];
-    let mut errors: Vec<ScheduleError> = Vec::new();
+    let root = starting_schedule(classes, classes_taken, config);
+    let evaluation = class.explain_requisites(&root);
+    print!("{evaluation}");
+    println!();
 
-    for _ in 0..(config.semesters) {
-        let (scheds_split, errors_split) = split_result_vec(step_schedules(scheds, &config));
-        scheds = scheds_split;
-        errors.extend(errors_split);
+    if evaluation.met() {
+        println!("{}", "Requisites met".green().bold());
+        return;
+    }
+    println!("{}", "Take any one of these to meet requisites:".bold());
+    for missing in evaluation.missing() {
+        println!("{}", missing.into_iter().join(", "));
     }
+}
 
-    scheds
+fn audit(classes: &[Arc<Class>], classes_taken: Vec<String>, config: &Config) {
+    let Some(degree) = &config.degree else {
+        eprintln!("No degree requirements, add them to degree.toml");
+        process::exit(1);
+    };
+
+    let root = Schedule::new(classes, Arc::new(classes_taken), config.starting_term);
+    for progress in root.audit(degree) {
+        println!("{progress}");
+    }
+}
+
+/// The schedule to start planning from: the locked terms of the plan in `config`, or nothing.
+fn starting_schedule(
+    classes: &[Arc<Class>],
+    classes_taken: Vec<String>,
+    config: &Config,
+) -> Schedule {
+    let taken = Arc::new(classes_taken);
+    let Some(path) = &config.plan else {
+        return Schedule::new(classes, taken, config.starting_term);
+    };
+    // rebuilding the plan checks its requisites
+    check_requisites(classes);
+    let plan = Plan::load(path).and_then(|plan| {
+        plan.locked(config.locked_terms)
+            .schedule(classes, taken, config)
+    });
+    match plan {
+        Ok(sched) => sched.lock(),
+        Err(err) => {
+            eprintln!("{} {}", "Could not continue plan:".red().bold(), err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Classes taken before `root` continues, including those in a saved plan, which count as taken
+/// with planning starting after them.
+fn planned_taken(root: &Schedule) -> Vec<String> {
+    root.taken()
         .iter()
-        .filter(|x| config.show_incomplete || x.is_complete(&config).is_ok())
+        .cloned()
+        .chain(
+            root.semesters()
+                .iter()
+                .flat_map(|sem| sem.0.iter().map(|class| class.name())),
+        )
+        .collect()
+}
+
+/// Warns about required classes which can't be taken in time, since no schedule can be complete,
+/// and stops if any requisites can't be parsed.
+fn print_warnings(classes: &[Arc<Class>], root: &Schedule, config: &Config) {
+    let graph = RequisiteGraph::new(classes, &planned_taken(root), root.next_term());
+    for problem in validate::reachability(&graph, config) {
+        eprintln!("{} {}", "Warning:".yellow().bold(), problem);
+    }
+    // every schedule checks requisites, so there's nothing to search without them
+    if !graph.invalid().is_empty() {
+        process::exit(1);
+    }
+}
+
+/// Stops if any requisites can't be parsed, for commands which check schedules without
+/// searching.
+fn check_requisites(classes: &[Arc<Class>]) {
+    let mut invalid = false;
+    for class in classes {
+        if let Err(err) = class.parsed_requisites() {
+            let problem = ValidationError::InvalidRequisites(class.name(), err);
+            eprintln!("{} {}", "Error:".red().bold(), problem);
+            invalid = true;
+        }
+    }
+    if invalid {
+        process::exit(1);
+    }
+}
+
+/// Everything found by searching for schedules.
+struct Search {
+    /// Schedules to show, sorted by total credits.
+    schedules: Vec<Schedule>,
+    errors: ErrorCounts,
+    duplicates: usize,
+    stopped: Option<(LimitReached, u8)>,
+    /// Schedules which still needed more terms when a limit was reached, and aren't shown.
+    unfinished: usize,
+}
+
+/// Adds one semester at a time to every schedule, until every semester has been added or a
+/// search limit is reached.
+fn search(root: Schedule, config: &Config) -> Search {
+    search_with(root, config, |_, _| {})
+}
+
+/// Like [`search`], but shows `visit` each schedule a semester is added to, along with the
+/// schedules it became.
+fn search_with(
+    root: Schedule,
+    config: &Config,
+    mut visit: impl FnMut(&Schedule, &[Schedule]),
+) -> Search {
+    let mut scheds: Vec<Schedule> = vec![root];
+    let mut errors = ErrorCounts::default();
+    let mut finished: Vec<Schedule> = Vec::new();
+    let mut seen: HashSet<ScheduleState> = HashSet::new();
+    let mut duplicates = 0;
+
+    let mut limits = Limits::new(config);
+    let mut stopped: Option<(LimitReached, u8)> = None;
+    let mut unfinished = 0;
+    'terms: for term in 1..=config.semesters {
+        if config.merge_duplicates {
+            let (merged, count) = merge_duplicates(scheds, &mut seen);
+            scheds = merged;
+            duplicates += count;
+        }
+        // schedules which already have enough credits or nothing left to take don't get more
+        // terms
+        let (done, open): (Vec<Schedule>, Vec<Schedule>) =
+            scheds.into_iter().partition(|sched| sched.finished(config));
+        finished.extend(done);
+        scheds = open;
+
+        // expand a chunk at a time so limits are checked partway through a term
+        let mut frontier = scheds.into_iter();
+        let mut next: Vec<Schedule> = Vec::new();
+        loop {
+            if let Err(limit) = limits.check(next.len() + frontier.len()) {
+                // only schedules which got every term or finished early are shown
+                let (done, open): (Vec<Schedule>, Vec<Schedule>) = next
+                    .into_iter()
+                    .partition(|sched| term == config.semesters || sched.finished(config));
+                unfinished = open.len() + frontier.len();
+                scheds = done;
+                stopped = Some((limit, term));
+                break 'terms;
+            }
+            let size = CHUNK_PER_THREAD * rayon::current_num_threads();
+            let chunk: Vec<Schedule> = frontier
+                .by_ref()
+                .take(limits.nodes_left().map_or(size, |left| left.min(size)))
+                .collect();
+            if chunk.is_empty() {
+                break;
+            }
+            limits.nodes += chunk.len();
+            for (sched, children) in step_schedules(chunk, config) {
+                let (scheds_split, errors_split) = split_result_vec(children);
+                visit(&sched, &scheds_split);
+                next.extend(scheds_split);
+                errors_split.iter().for_each(|err| errors.add(err));
+            }
+            limits.report(
+                term,
+                config.semesters,
+                next.len() + frontier.len(),
+                errors.total,
+            );
+        }
+        scheds = next;
+    }
+    scheds.extend(finished);
+
+    let schedules: Vec<Schedule> = scheds
+        .into_iter()
+        .filter(|x| config.show_incomplete || x.is_complete(config).is_ok())
         .sorted_by(|a, b| a.total_credits().cmp(&b.total_credits()))
-        .for_each(|x| {
-            println!("{}", x);
+        .collect();
+    Search {
+        schedules,
+        errors,
+        duplicates,
+        stopped,
+        unfinished,
+    }
+}
+
+fn generate(classes: Vec<Arc<Class>>, classes_taken: Vec<String>, config: Config) {
+    let root = starting_schedule(&classes, classes_taken, &config);
+    print_warnings(&classes, &root, &config);
+
+    let search = search(root, &config);
+    if config.collapse_electives {
+        for cluster in collapse::collapse(&search.schedules, &config) {
+            println!("{cluster}");
+            println!();
+        }
+    } else {
+        for (number, sched) in search.schedules.iter().enumerate() {
+            println!("{}", format!("Schedule {}", number + 1).dim());
+            println!("{}", sched);
             println!(
                 "{} {}",
                 "Complete:".yellow().bold(),
-                x.completeness_display(&config)
+                sched.completeness_display(&config)
             );
-            println!("");
-        });
+            println!();
+        }
+    }
+
+    print_summary(&search.errors, search.duplicates);
+
+    let consequence = format!(
+        "only finished schedules are shown, and {} unfinished ones weren't",
+        search.unfinished
+    );
+    print_stopped(search.stopped, &config, &consequence);
+}
+
+/// Notes that a search limit was reached, and what that means for the output above it.
+fn print_stopped(stopped: Option<(LimitReached, u8)>, config: &Config, consequence: &str) {
+    if let Some((limit, term)) = stopped {
+        println!();
+        println!(
+            "{} {}",
+            "Stopped early:".yellow().bold(),
+            format!(
+                "{limit} while adding term {term} of {}, so {consequence}",
+                config.semesters
+            )
+            .yellow()
+        );
+    }
+}
+
+/// Saves the `number`th schedule listed (counting from 1) as a plan.
+fn save(
+    number: usize,
+    path: &str,
+    classes: Vec<Arc<Class>>,
+    classes_taken: Vec<String>,
+    config: Config,
+) {
+    // the schedule is found again by searching the same way, so it has to be numbered and found
+    // the same way every time
+    if config.stream || config.collapse_electives {
+        eprintln!(
+            "Schedules aren't numbered with stream or collapse_electives, turn them off to save \
+             one"
+        );
+        process::exit(1);
+    }
+    if config.max_nodes.is_some() || config.time_limit.is_some() || config.max_schedules.is_some() {
+        eprintln!(
+            "Schedules found with max_nodes, time_limit or max_schedules can change from run to \
+             run, remove them to save one"
+        );
+        process::exit(1);
+    }
+
+    let root = starting_schedule(&classes, classes_taken, &config);
+    print_warnings(&classes, &root, &config);
+    let search = search(root, &config);
+    let Some(sched) = number
+        .checked_sub(1)
+        .and_then(|index| search.schedules.get(index))
+    else {
+        eprintln!("There are only {} schedules", search.schedules.len());
+        process::exit(1);
+    };
+
+    if let Err(err) = Plan::from_schedule(sched).save(path) {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+    println!("{sched}");
+    println!("{}", format!("Saved schedule {number} to {path}").green());
+}
+
+fn diff_plans(
+    paths: &[String],
+    classes: &[Arc<Class>],
+    classes_taken: Vec<String>,
+    config: &Config,
+) {
+    let [first, second] = paths else {
+        eprintln!("Usage: diff <plan> <plan>");
+        process::exit(1);
+    };
+    let (first, second) = match (Plan::load(first), Plan::load(second)) {
+        (Ok(first), Ok(second)) => (first, second),
+        (Err(err), _) | (_, Err(err)) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+    };
+
+    check_requisites(classes);
+
+    for term in plan::diff(&first, &second) {
+        println!("{term}");
+    }
+    println!();
+
+    let (before, after) = (first.total_credits(), second.total_credits());
+    println!(
+        "{} {before} -> {after} ({:+})",
+        "Total credits:".yellow().bold(),
+        after as i32 - before as i32
+    );
+    let taken = Arc::new(classes_taken);
+    let completeness = |plan: &Plan| match plan.schedule(classes, taken.clone(), config) {
+        Ok(sched) => sched.completeness_display(config).to_string(),
+        Err(err) => format!("No, invalid: {err}").red().to_string(),
+    };
+    println!(
+        "{} {} -> {}",
+        "Complete:".yellow().bold(),
+        completeness(&first),
+        completeness(&second)
+    );
+}
+
+fn stream(classes: Vec<Arc<Class>>, classes_taken: Vec<String>, config: Config) {
+    let root = starting_schedule(&classes, classes_taken, &config);
+    print_warnings(&classes, &root, &config);
+
+    let mut stream = Stream::new(&config, io::stdout().lock());
+    if stream.run(root).is_err() {
+        // the output was closed, eg. by `head`, so nothing else will be read
+        return;
+    }
+
+    print_summary(&stream.errors, stream.duplicates);
+    print_stopped(stream.stopped, &config, "some schedules weren't found");
+}
+
+fn sample(classes: Vec<Arc<Class>>, classes_taken: Vec<String>, config: Config, count: usize) {
+    let root = starting_schedule(&classes, classes_taken, &config);
+    print_warnings(&classes, &root, &config);
+
+    let samples = sample::sample(&root, &config, count);
+    for sched in samples.schedules.iter() {
+        println!("{}", sched);
+        println!(
+            "{} {}",
+            "Complete:".yellow().bold(),
+            sched.completeness_display(&config)
+        );
+        println!();
+    }
 
     println!(
-        "{}{}",
-        "Errors".bold().bright_red(),
-        "".white().dim().linger()
+        "{}",
+        format!(
+            "Sampled {} of {count} schedules in {} attempts (seed {})",
+            samples.schedules.len(),
+            samples.attempts,
+            samples.seed
+        )
+        .dim()
     );
-    let err_counts = errors.iter().counts();
-    err_counts
+    print_stopped(samples.stopped, &config, "fewer schedules were sampled");
+}
+
+fn solve(classes: Vec<Arc<Class>>, classes_taken: Vec<String>, config: Config) {
+    let root = starting_schedule(&classes, classes_taken, &config);
+    print_warnings(&classes, &root, &config);
+
+    match solve::solve(&classes, &root, &config) {
+        Ok(sched) => {
+            println!("{}", sched);
+            println!(
+                "{} {}",
+                "Complete:".yellow().bold(),
+                sched.completeness_display(&config)
+            );
+        }
+        Err(err) => {
+            eprintln!("{}", err.red());
+            process::exit(1);
+        }
+    }
+}
+
+/// Prints how many schedules were merged and why schedules were thrown out.
+fn print_summary(errors: &ErrorCounts, duplicates: usize) {
+    if duplicates > 0 {
+        println!(
+            "{}",
+            format!("Merged {duplicates} schedules which only differed in term order").dim()
+        );
+        println!();
+    }
+    print_counts("Errors", &errors.by_kind);
+    println!();
+    print_counts("Errors by class and term", &errors.by_class);
+    println!();
+    print_counts("Errors by term", &errors.by_term);
+}
+
+fn print_counts<T: Display>(title: &str, counts: &HashMap<T, usize>) {
+    println!("{}{}", title.bold().bright_red(), "".white().dim().linger());
+    counts
         .iter()
-        .sorted_by(|a, b| (b.1).cmp(a.1))
-        .for_each(|(&err, count)| println!("{count}: {err}"));
+        .sorted_by(|a, b| {
+            (b.1)
+                .cmp(a.1)
+                .then_with(|| a.0.to_string().cmp(&b.0.to_string()))
+        })
+        .for_each(|(item, count)| println!("{count}: {item}"));
+}
+
+fn main() {
+    let (classes, classes_taken, config) = load_inputs();
+    if let Some(threads) = config.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .unwrap();
+    }
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None if config.stream => stream(classes, classes_taken, config),
+        None => generate(classes, classes_taken, config),
+        Some("validate") => validate_inputs(&classes, classes_taken, &config),
+        Some("requisites") => {
+            show_requisites(&args[1..].join(" "), &classes, classes_taken, &config)
+        }
+        Some("sample") => {
+            let count = match args.get(1).map(|count| count.parse()) {
+                None => 20,
+                Some(Ok(count)) => count,
+                Some(Err(_)) => {
+                    eprintln!("Usage: sample [number of schedules]");
+                    process::exit(1);
+                }
+            };
+            sample(classes, classes_taken, config, count)
+        }
+        Some("save") => {
+            let (Some(Ok(number)), Some(path)) = (args.get(1).map(|n| n.parse()), args.get(2))
+            else {
+                eprintln!("Usage: save <schedule number> <plan>");
+                process::exit(1);
+            };
+            save(number, path, classes, classes_taken, config)
+        }
+        Some("diff") => diff_plans(&args[1..], &classes, classes_taken, &config),
+        Some("solve") => solve(classes, classes_taken, config),
+        Some("audit") => audit(&classes, classes_taken, &config),
+        Some("explain") => explain_class(&args[1..].join(" "), &classes, classes_taken, &config),
+        Some(other) => {
+            eprintln!("Unknown command: {other}");
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{classes, config, layout, root, taken};
+
+    const CATALOG: &str = "ABC,100,3,false,,Fall|Spring,
+                           ABC,101,3,true,,Fall|Spring,
+                           ABC,102,3,false,,Fall|Spring,";
+
+    #[test]
+    fn stop_at_total_credits() {
+        let classes = classes(CATALOG);
+        let config = config(3, 3, 3, "total_credits = 6\nshow_incomplete = true");
+        let search = search(root(&classes, &taken(&[]), &config), &config);
+        // schedules without ABC 101 stop too, once they have the credits
+        assert_eq!(search.schedules.len(), 6);
+        assert!(search
+            .schedules
+            .iter()
+            .all(|sched| sched.semesters().len() == 2));
+        let incomplete = search
+            .schedules
+            .iter()
+            .filter(|sched| sched.is_complete(&config).is_err())
+            .count();
+        assert_eq!(incomplete, 2);
+    }
+
+    #[test]
+    fn taken_credits_from_catalog() {
+        let classes = classes(CATALOG);
+        let config = config(3, 3, 3, "total_credits = 8\ntaken_credits = 2");
+        let root = root(&classes, &taken(&["ABC 100", "XYZ 100"]), &config);
+        // ABC 100 is worth 3 credits in the catalog, but XYZ 100 isn't listed
+        assert_eq!(root.earned_credits(&config), 5);
+        let search = search(root.clone(), &config);
+        assert!(search
+            .schedules
+            .iter()
+            .all(|sched| sched.semesters().len() == 1));
+        assert!(search
+            .schedules
+            .iter()
+            .any(|sched| layout(sched) == [["ABC 101"]] && sched.is_complete(&config).is_ok()));
+        // a taken class is never taken again, so its credits aren't counted twice
+        assert!(search
+            .schedules
+            .iter()
+            .flat_map(|sched| layout(sched).concat())
+            .all(|name| name != "ABC 100"));
+        let solved = solve::solve(&classes, &root, &config).unwrap();
+        assert!(!layout(&solved).concat().contains(&"ABC 100".to_owned()));
+    }
+
+    #[test]
+    fn merging_duplicates() {
+        let classes = classes(CATALOG);
+        let count = |merge: bool| {
+            let config = config(3, 3, 3, &format!("merge_duplicates = {merge}"));
+            let search = search(root(&classes, &taken(&[]), &config), &config);
+            (search.schedules.len(), search.duplicates)
+        };
+        // every order of the three classes, or one for each class taken last
+        assert_eq!(count(false), (6, 0));
+        assert_eq!(count(true), (3, 3));
+    }
+
+    #[test]
+    fn stop_at_max_nodes() {
+        let classes = classes(CATALOG);
+        let config = config(3, 3, 3, "show_incomplete = true\nmax_nodes = 5");
+        // 1 schedule is expanded in the first term, 3 in the second and 6 in the third
+        let mut expanded = 0;
+        let search = search_with(root(&classes, &taken(&[]), &config), &config, |_, _| {
+            expanded += 1
+        });
+        assert_eq!(expanded, 5);
+        assert_eq!(search.stopped, Some((LimitReached::Nodes(5), 3)));
+        // the one schedule expanded in the last term is finished, the other five aren't
+        assert_eq!(search.schedules.len(), 1);
+        assert_eq!(search.schedules[0].semesters().len(), 3);
+        assert_eq!(search.unfinished, 5);
+    }
+
+    #[test]
+    fn stop_before_last_term() {
+        let classes = classes(CATALOG);
+        let config = config(3, 3, 3, "show_incomplete = true\nmax_nodes = 2");
+        let search = search(root(&classes, &taken(&[]), &config), &config);
+        assert_eq!(search.stopped, Some((LimitReached::Nodes(2), 2)));
+        // two schedules made from the one expanded in the second term, and two not expanded
+        assert!(search.schedules.is_empty());
+        assert_eq!(search.unfinished, 2 + 2);
+    }
+
+    #[test]
+    fn same_order_on_any_number_of_threads() {
+        let classes = classes(
+            "ABC,100,3,false,,Fall|Spring,
+             ABC,101,3,true,,Fall|Spring,ABC 100
+             ABC,102,3,false,,Fall|Spring,
+             ABC,103,1-3,false,,Fall|Spring,
+             ABC,104,4,false,,Spring,ABC 102 or concurrently
+             ABC,105,2,false,,Fall,",
+        );
+        for extra in ["", "max_nodes = 40"] {
+            let config = config(3, 8, 3, extra);
+            let rendered = |threads: usize| {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .unwrap();
+                let search = pool.install(|| search(root(&classes, &taken(&[]), &config), &config));
+                let schedules: Vec<String> = search
+                    .schedules
+                    .iter()
+                    .map(|sched| format!("{sched}"))
+                    .collect();
+                (schedules, search.errors, search.stopped, search.unfinished)
+            };
+            let single = rendered(1);
+            // enough schedules for several chunks, so threads have to split them up
+            assert!(single.0.len() > CHUNK_PER_THREAD * 4, "{extra}");
+            assert_eq!(single, rendered(4), "{extra}");
+        }
+    }
 }
@@ -0,0 +1,448 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use std::{fs, io, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use yansi::Paint;
+
+use crate::class::Class;
+use crate::config::Config;
+use crate::data::{Schedule, ScheduleError, Semester, Term};
+
+#[derive(Error, Debug)]
+pub enum PlanError {
+    #[error("Could not read {0}: {1}")]
+    Read(String, io::Error),
+    #[error("Could not parse {0}: {1}")]
+    Parse(String, serde_json::Error),
+    #[error("Could not write {0}: {1}")]
+    Write(String, io::Error),
+    #[error("{0} is not in input.csv")]
+    UnknownClass(String),
+    #[error("{0} is taken for {1} credits, but it's worth {2}")]
+    WrongCredits(String, u8, String),
+    #[error("The plan starts in {0}, before the starting term {1}")]
+    TooEarly(Term, Term),
+    #[error("The plan skips from {0} to {1}, but its terms must follow each other")]
+    NotConsecutive(Term, Term),
+    #[error("{0}")]
+    Invalid(#[from] ScheduleError),
+}
+
+/// A schedule saved as JSON, so it can be compared with other plans later.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub semesters: Vec<PlannedSemester>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedSemester {
+    pub term: Term,
+    pub classes: Vec<PlannedClass>,
+}
+
+/// A class in a plan, with the credits it's taken for in case it's a variable-credit class.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedClass {
+    pub name: String,
+    pub credits: u8,
+}
+
+impl Plan {
+    pub fn from_schedule(sched: &Schedule) -> Self {
+        let semesters = sched
+            .semesters()
+            .iter()
+            .map(|sem| PlannedSemester {
+                term: sem.1,
+                classes: sem
+                    .0
+                    .iter()
+                    .map(|class| PlannedClass {
+                        name: class.name(),
+                        credits: class.credits(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Plan { semesters }
+    }
+
+    pub fn load(path: &str) -> Result<Self, PlanError> {
+        let json = fs::read_to_string(path).map_err(|err| PlanError::Read(path.to_owned(), err))?;
+        serde_json::from_str(&json).map_err(|err| PlanError::Parse(path.to_owned(), err))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), PlanError> {
+        let json = serde_json::to_string_pretty(self).expect("Plans can always be written as JSON");
+        fs::write(path, json + "\n").map_err(|err| PlanError::Write(path.to_owned(), err))
+    }
+
+    /// The plan's first `terms` terms, or all of them.
+    pub fn locked(mut self, terms: Option<u8>) -> Self {
+        if let Some(terms) = terms {
+            self.semesters.truncate(terms.into());
+        }
+        self
+    }
+
+    pub fn total_credits(&self) -> u16 {
+        self.semesters.iter().map(PlannedSemester::credits).sum()
+    }
+
+    /// Rebuilds the schedule from the classes in `input.csv`, checking each semester the same
+    /// way generated schedules are checked. The terms must follow each other from the starting
+    /// term on, and each class must be taken for credits it's worth.
+    pub fn schedule(
+        &self,
+        classes: &[Arc<Class>],
+        taken: Arc<Vec<String>>,
+        config: &Config,
+    ) -> Result<Schedule, PlanError> {
+        let first = self
+            .semesters
+            .first()
+            .map_or(config.starting_term, |sem| sem.term);
+        if first < config.starting_term {
+            return Err(PlanError::TooEarly(first, config.starting_term));
+        }
+        for (before, after) in self.semesters.iter().zip(self.semesters.iter().skip(1)) {
+            if after.term != before.term.next() {
+                return Err(PlanError::NotConsecutive(before.term, after.term));
+            }
+        }
+        let mut sched = Schedule::new(classes, taken, first);
+        for semester in self.semesters.iter() {
+            let picked = semester
+                .classes
+                .iter()
+                .map(|planned| {
+                    let class = classes
+                        .iter()
+                        .find(|class| class.name() == planned.name)
+                        .ok_or_else(|| PlanError::UnknownClass(planned.name.clone()))?;
+                    let (min, max) = class.credit_range();
+                    if !(min..=max).contains(&planned.credits) {
+                        let worth = match class.is_variable() {
+                            true => format!("{min}-{max} credits"),
+                            false => format!("{min} credits"),
+                        };
+                        return Err(PlanError::WrongCredits(
+                            planned.name.clone(),
+                            planned.credits,
+                            worth,
+                        ));
+                    }
+                    Ok(match class.is_variable() {
+                        true => Arc::new(class.with_credits(planned.credits)),
+                        false => class.clone(),
+                    })
+                })
+                .collect::<Result<Vec<Arc<Class>>, PlanError>>()?;
+            sched = sched.child(Semester::new(picked, semester.term).verify(config)?)?;
+        }
+        Ok(sched)
+    }
+
+    /// Terms each class is taken in, earliest first.
+    fn placements(&self) -> BTreeMap<&str, Vec<Term>> {
+        let mut placements: BTreeMap<&str, Vec<Term>> = BTreeMap::new();
+        for semester in self.semesters.iter() {
+            for class in semester.classes.iter() {
+                placements
+                    .entry(class.name.as_str())
+                    .or_default()
+                    .push(semester.term);
+            }
+        }
+        placements
+    }
+
+    fn credits_in(&self, term: Term) -> u16 {
+        self.semesters
+            .iter()
+            .filter(|sem| sem.term == term)
+            .map(PlannedSemester::credits)
+            .sum()
+    }
+}
+
+impl PlannedSemester {
+    pub fn credits(&self) -> u16 {
+        self.classes.iter().map(|class| class.credits as u16).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(String),
+    Dropped(String),
+    MovedIn(String, Term),
+    MovedOut(String, Term),
+}
+
+/// How one term changes from the first plan to the second.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermDiff {
+    pub term: Term,
+    pub credits: (u16, u16),
+    pub changes: Vec<Change>,
+}
+
+/// Compares two plans term by term. A class taken in both plans but in different terms counts
+/// as moved; repeated classes are matched up in the order they're taken.
+pub fn diff(first: &Plan, second: &Plan) -> Vec<TermDiff> {
+    let terms: BTreeSet<Term> = first
+        .semesters
+        .iter()
+        .chain(second.semesters.iter())
+        .map(|sem| sem.term)
+        .collect();
+    let mut diffs: Vec<TermDiff> = terms
+        .iter()
+        .map(|&term| TermDiff {
+            term,
+            credits: (first.credits_in(term), second.credits_in(term)),
+            changes: Vec::new(),
+        })
+        .collect();
+    let mut change = |term: Term, change: Change| {
+        if let Some(diff) = diffs.iter_mut().find(|diff| diff.term == term) {
+            diff.changes.push(change);
+        }
+    };
+
+    let before = first.placements();
+    let after = second.placements();
+    let names: BTreeSet<&str> = before.keys().chain(after.keys()).copied().collect();
+    for name in names {
+        let before = before.get(name).map_or(&[][..], Vec::as_slice);
+        let after = after.get(name).map_or(&[][..], Vec::as_slice);
+        for (&from, &to) in before.iter().zip(after.iter()) {
+            if from != to {
+                change(from, Change::MovedOut(name.to_owned(), to));
+                change(to, Change::MovedIn(name.to_owned(), from));
+            }
+        }
+        for &term in before.iter().skip(after.len()) {
+            change(term, Change::Dropped(name.to_owned()));
+        }
+        for &term in after.iter().skip(before.len()) {
+            change(term, Change::Added(name.to_owned()));
+        }
+    }
+    diffs
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(name) => write!(f, "{}", format!("+ {name}").green()),
+            Change::Dropped(name) => write!(f, "{}", format!("- {name}").red()),
+            Change::MovedIn(name, from) => {
+                write!(f, "{}", format!("~ {name} (moved from {from})").yellow())
+            }
+            Change::MovedOut(name, to) => {
+                write!(f, "{}", format!("~ {name} (moved to {to})").yellow())
+            }
+        }
+    }
+}
+
+impl Display for TermDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (before, after) = self.credits;
+        let delta = after as i32 - before as i32;
+        write!(
+            f,
+            "{} {}{} ",
+            self.term.season.bold().blue(),
+            self.term.year.bold().blue(),
+            ":".bold().blue()
+        )?;
+        match delta {
+            0 => write!(f, "{}", format!("{after} credits").bright_black())?,
+            _ => write!(
+                f,
+                "{}",
+                format!("{before} -> {after} credits ({delta:+})").bright_black()
+            )?,
+        }
+        if self.changes.is_empty() {
+            write!(f, "{}", ", no changes".dim())?;
+        }
+        for change in self.changes.iter() {
+            write!(f, "\n  {change}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::TermSeason;
+    use crate::testing::{classes, config};
+
+    const FALL: Term = Term {
+        season: TermSeason::Fall,
+        year: 2024,
+    };
+    const SPRING: Term = Term {
+        season: TermSeason::Spring,
+        year: 2025,
+    };
+
+    fn plan(semesters: &[(Term, &[&str])]) -> Plan {
+        Plan {
+            semesters: semesters
+                .iter()
+                .map(|(term, names)| PlannedSemester {
+                    term: *term,
+                    classes: names
+                        .iter()
+                        .map(|name| PlannedClass {
+                            name: name.to_string(),
+                            credits: 3,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    fn changes(first: &Plan, second: &Plan) -> Vec<(Term, Vec<Change>)> {
+        diff(first, second)
+            .into_iter()
+            .map(|diff| (diff.term, diff.changes))
+            .collect()
+    }
+
+    #[test]
+    fn moved_added_and_dropped() {
+        let first = plan(&[(FALL, &["ABC 100", "ABC 101"]), (SPRING, &["ABC 200"])]);
+        let second = plan(&[(FALL, &["ABC 100", "ABC 200"]), (SPRING, &["ABC 102"])]);
+        assert_eq!(
+            changes(&first, &second),
+            [
+                (
+                    FALL,
+                    vec![
+                        Change::Dropped("ABC 101".to_owned()),
+                        Change::MovedIn("ABC 200".to_owned(), SPRING),
+                    ]
+                ),
+                (
+                    SPRING,
+                    vec![
+                        Change::Added("ABC 102".to_owned()),
+                        Change::MovedOut("ABC 200".to_owned(), FALL),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(diff(&first, &second)[0].credits, (6, 6));
+    }
+
+    #[test]
+    fn repeated_classes() {
+        // the first time it's taken stays put, and the second is dropped
+        let first = plan(&[(FALL, &["MUS 100"]), (SPRING, &["MUS 100"])]);
+        let second = plan(&[(FALL, &["MUS 100"]), (SPRING, &["ABC 100"])]);
+        assert_eq!(
+            changes(&first, &second),
+            [
+                (FALL, vec![]),
+                (
+                    SPRING,
+                    vec![
+                        Change::Added("ABC 100".to_owned()),
+                        Change::Dropped("MUS 100".to_owned()),
+                    ]
+                ),
+            ]
+        );
+
+        // taken once more, starting a term later
+        let second = plan(&[(SPRING, &["MUS 100"])]);
+        assert_eq!(
+            changes(&first, &second),
+            [
+                (FALL, vec![Change::MovedOut("MUS 100".to_owned(), SPRING)]),
+                (
+                    SPRING,
+                    vec![
+                        Change::MovedIn("MUS 100".to_owned(), FALL),
+                        Change::Dropped("MUS 100".to_owned()),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_plans() {
+        let first = plan(&[(FALL, &["ABC 100"]), (SPRING, &["ABC 101", "ABC 102"])]);
+        assert!(changes(&first, &first.clone())
+            .iter()
+            .all(|(_, changes)| changes.is_empty()));
+        assert_eq!(diff(&first, &first)[1].credits, (6, 6));
+    }
+
+    fn schedule(plan: &Plan) -> Result<Schedule, PlanError> {
+        let classes = classes(
+            "ABC,100,3,true,,Fall|Spring,
+             ABC,101,3,false,,Fall|Spring,
+             ABC,102,3,false,,Fall|Spring,
+             ABC,103,1-3,false,,Fall|Spring,",
+        );
+        plan.schedule(&classes, Arc::new(Vec::new()), &config(1, 9, 4, ""))
+    }
+
+    fn with_credits(mut plan: Plan, credits: u8) -> Plan {
+        plan.semesters[0].classes[0].credits = credits;
+        plan
+    }
+
+    #[test]
+    fn planned_credits() {
+        let fixed = plan(&[(FALL, &["ABC 100"])]);
+        assert!(schedule(&fixed).is_ok());
+        assert!(matches!(
+            schedule(&with_credits(fixed, 2)),
+            Err(PlanError::WrongCredits(name, 2, _)) if name == "ABC 100"
+        ));
+
+        let variable = plan(&[(FALL, &["ABC 103"])]);
+        let sched = schedule(&with_credits(variable.clone(), 2)).unwrap();
+        assert_eq!(sched.semesters()[0].credits(), 2);
+        assert!(matches!(
+            schedule(&with_credits(variable.clone(), 0)),
+            Err(PlanError::WrongCredits(..))
+        ));
+        assert!(matches!(
+            schedule(&with_credits(variable, 4)),
+            Err(PlanError::WrongCredits(..))
+        ));
+    }
+
+    #[test]
+    fn planned_terms() {
+        let later = FALL.next().next();
+        assert!(schedule(&plan(&[(SPRING, &["ABC 100"]), (later, &["ABC 101"])])).is_ok());
+
+        let early = Term {
+            season: TermSeason::Spring,
+            year: 2024,
+        };
+        assert!(matches!(
+            schedule(&plan(&[(early, &["ABC 100"])])),
+            Err(PlanError::TooEarly(term, FALL)) if term == early
+        ));
+        assert!(matches!(
+            schedule(&plan(&[(FALL, &["ABC 100"]), (later, &["ABC 101"])])),
+            Err(PlanError::NotConsecutive(FALL, term)) if term == later
+        ));
+    }
+}
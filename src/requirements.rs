@@ -1,17 +1,27 @@
-pub use parser::parse;
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use yansi::Paint;
+
+pub use parser::{parse, try_parse};
 
 pub trait TestRequisite {
     fn has_prerequisite(&self, requisite: &RequisiteName) -> bool;
     fn has_corequisite(&self, requisite: &RequisiteName) -> bool;
+    /// Whether the requisite is being taken in the same term, and not before. Classes already
+    /// taken count too, since the term they were taken in isn't known.
+    fn has_concurrent(&self, requisite: &RequisiteName) -> bool;
 }
 
 // sample
+#[cfg(test)]
 #[derive(Default)]
 pub struct RequesiteRegistry<'a> {
     prerequisites: &'a [RequisiteName],
     corequisites: &'a [RequisiteName],
 }
 
+#[cfg(test)]
 impl TestRequisite for RequesiteRegistry<'_> {
     fn has_prerequisite(&self, requisite: &RequisiteName) -> bool {
         self.prerequisites.contains(requisite)
@@ -19,21 +29,41 @@ impl TestRequisite for RequesiteRegistry<'_> {
     fn has_corequisite(&self, requisite: &RequisiteName) -> bool {
         self.corequisites.contains(requisite)
     }
+    fn has_concurrent(&self, requisite: &RequisiteName) -> bool {
+        self.corequisites.contains(requisite) && !self.prerequisites.contains(requisite)
+    }
 }
 
 pub trait EvalExpression<T: ?Sized> {
     fn eval(&self, requisites_list: &T) -> bool;
 }
 
+/// Like [`EvalExpression`], but keeps track of how each part of the expression was evaluated.
+pub trait ExplainExpression<T: ?Sized> {
+    fn explain(&self, requisites_list: &T) -> Evaluation;
+}
+
+/// The result of evaluating an expression, as a tree. "and"/"or" groups with a single member are
+/// collapsed into that member.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Evaluation {
+    Req(Requisite, bool),
+    Any(Vec<Evaluation>, bool),
+    All(Vec<Evaluation>, bool),
+}
+
 pub type RequisiteName = String; // probably need to change either this or Class
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum RequisiteType {
     Pre,
+    /// Taken before or in the same term ("or concurrently").
     Co,
+    /// Taken in exactly the same term ("concurrently"), like a lecture and its lab.
+    Concurrent,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub struct Requisite {
     pub typ: RequisiteType,
     pub name: RequisiteName,
@@ -47,6 +77,7 @@ impl<T: TestRequisite + ?Sized> EvalExpression<T> for Requisite {
                 requisites_list.has_prerequisite(&self.name)
                     || requisites_list.has_corequisite(&self.name)
             }
+            RequisiteType::Concurrent => requisites_list.has_concurrent(&self.name),
         }
     }
 }
@@ -93,20 +124,408 @@ impl<T: TestRequisite + ?Sized> EvalExpression<T> for Expression {
     }
 }
 
+impl<T: TestRequisite + ?Sized> ExplainExpression<T> for Requisite {
+    fn explain(&self, requisites_list: &T) -> Evaluation {
+        Evaluation::Req(self.clone(), self.eval(requisites_list))
+    }
+}
+
+impl<T: TestRequisite + ?Sized> ExplainExpression<T> for Primary {
+    fn explain(&self, requisites_list: &T) -> Evaluation {
+        match self {
+            Primary::Req(requisite) => requisite.explain(requisites_list),
+            Primary::Expr(expression) => expression.explain(requisites_list),
+        }
+    }
+}
+
+impl<T: TestRequisite + ?Sized> ExplainExpression<T> for OrExpression {
+    fn explain(&self, requisites_list: &T) -> Evaluation {
+        let mut children: Vec<Evaluation> = self
+            .0
+            .iter()
+            .map(|expr| expr.explain(requisites_list))
+            .collect();
+        if children.len() == 1 {
+            return children.pop().unwrap();
+        }
+        let met = children.iter().any(Evaluation::met);
+        Evaluation::Any(children, met)
+    }
+}
+
+impl<T: TestRequisite + ?Sized> ExplainExpression<T> for AndExpression {
+    fn explain(&self, requisites_list: &T) -> Evaluation {
+        let mut children: Vec<Evaluation> = self
+            .0
+            .iter()
+            .map(|expr| expr.explain(requisites_list))
+            .collect();
+        if children.len() == 1 {
+            return children.pop().unwrap();
+        }
+        let met = children.iter().all(Evaluation::met);
+        Evaluation::All(children, met)
+    }
+}
+
+impl<T: TestRequisite + ?Sized> ExplainExpression<T> for Expression {
+    fn explain(&self, requisites_list: &T) -> Evaluation {
+        self.0.explain(requisites_list)
+    }
+}
+
+impl Evaluation {
+    pub fn met(&self) -> bool {
+        match self {
+            Evaluation::Req(_, met) | Evaluation::Any(_, met) | Evaluation::All(_, met) => *met,
+        }
+    }
+
+    /// The smallest sets of classes which, if taken, would make the expression true. Empty if
+    /// the expression is already true.
+    pub fn missing(&self) -> Vec<BTreeSet<RequisiteName>> {
+        if self.met() {
+            return Vec::new();
+        }
+        minimize(self.options())
+    }
+
+    /// Every set of classes which would make this part of the expression true.
+    fn options(&self) -> Vec<BTreeSet<RequisiteName>> {
+        match self {
+            Evaluation::Req(_, true) => vec![BTreeSet::new()],
+            Evaluation::Req(requisite, false) => vec![BTreeSet::from([requisite.name.clone()])],
+            Evaluation::Any(children, _) => {
+                minimize(children.iter().flat_map(Evaluation::options).collect())
+            }
+            Evaluation::All(children, _) => {
+                children.iter().fold(vec![BTreeSet::new()], |acc, child| {
+                    let options = child.options();
+                    let combined = acc
+                        .iter()
+                        .flat_map(|a| options.iter().map(move |b| a.union(b).cloned().collect()))
+                        .collect();
+                    minimize(combined)
+                })
+            }
+        }
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let mark = if self.met() {
+            "✓".green()
+        } else {
+            "✗".red()
+        };
+        write!(f, "{:indent$}{} ", "", mark, indent = depth * 2)?;
+        match self {
+            Evaluation::Req(requisite, _) => writeln!(f, "{requisite}"),
+            Evaluation::Any(children, _) | Evaluation::All(children, _) => {
+                match self {
+                    Evaluation::Any(..) => writeln!(f, "{}", "any of:".bold())?,
+                    _ => writeln!(f, "{}", "all of:".bold())?,
+                }
+                for child in children {
+                    child.fmt_indented(f, depth + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Drops duplicate sets and sets which contain another set.
+fn minimize<T: Ord>(mut sets: Vec<BTreeSet<T>>) -> Vec<BTreeSet<T>> {
+    sets.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+    let mut out: Vec<BTreeSet<T>> = Vec::new();
+    for set in sets {
+        if !out.iter().any(|smaller| smaller.is_subset(&set)) {
+            out.push(set);
+        }
+    }
+    out
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+impl Display for Requisite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.typ {
+            RequisiteType::Pre => write!(f, "{}", self.name),
+            RequisiteType::Co => write!(f, "{} or concurrently", self.name),
+            RequisiteType::Concurrent => write!(f, "{} concurrently", self.name),
+        }
+    }
+}
+
+impl OrExpression {
+    /// Every requisite named anywhere in the expression, in order of appearance.
+    pub fn requisites(&self) -> Vec<&Requisite> {
+        let mut out = Vec::new();
+        for primary in self.0.iter() {
+            match primary {
+                Primary::Req(requisite) => out.push(requisite),
+                Primary::Expr(expression) => out.extend(expression.requisites()),
+            }
+        }
+        out
+    }
+}
+
+impl Expression {
+    /// Every requisite named anywhere in the expression, in order of appearance.
+    pub fn requisites(&self) -> Vec<&Requisite> {
+        self.0 .0.iter().flat_map(|or| or.requisites()).collect()
+    }
+
+    /// Requisites which aren't met, from each of the "and" clauses which aren't met.
+    pub fn unmet<T: TestRequisite + ?Sized>(&self, requisites_list: &T) -> Vec<&Requisite> {
+        self.0
+             .0
+            .iter()
+            .filter(|or| !or.eval(requisites_list))
+            .flat_map(|or| or.requisites())
+            .filter(|req| !req.eval(requisites_list))
+            .collect()
+    }
+
+    /// An equivalent expression with redundant parts removed: groups with a single member are
+    /// flattened into their parent, duplicates are removed, and terms absorbed by another term
+    /// (like `B` in `A and (A or B)`) are dropped.
+    pub fn normalize(&self) -> Expression {
+        let mut clauses: Vec<OrExpression> = Vec::new();
+        for or in self.0 .0.iter() {
+            let Some(or) = or.normalize() else {
+                continue;
+            };
+            match or.0.as_slice() {
+                [Primary::Expr(expression)] => clauses.extend(expression.0 .0.iter().cloned()),
+                _ => clauses.push(or),
+            }
+        }
+
+        let clauses = absorb(clauses, |or: &OrExpression| or.0.clone());
+        Expression(AndExpression(clauses))
+    }
+
+    /// The expression as an "or" of "and" groups of single requisites (disjunctive normal form).
+    pub fn to_dnf(&self) -> Expression {
+        let terms = minimize(self.dnf_terms());
+        if terms.iter().any(BTreeSet::is_empty) {
+            return Expression::default();
+        }
+        let primaries = terms
+            .into_iter()
+            .map(|term| {
+                let mut clauses: Vec<OrExpression> = term
+                    .into_iter()
+                    .map(|req| OrExpression(vec![Primary::Req(req)]))
+                    .collect();
+                if clauses.len() == 1 {
+                    return clauses.pop().unwrap().0.pop().unwrap();
+                }
+                Primary::Expr(Expression(AndExpression(clauses)))
+            })
+            .collect();
+        Expression(AndExpression(vec![OrExpression(primaries)]))
+    }
+
+    /// The expression as an "and" of "or" groups of single requisites (conjunctive normal form).
+    pub fn to_cnf(&self) -> Expression {
+        let clauses = minimize(self.cnf_clauses())
+            .into_iter()
+            .map(|clause| OrExpression(clause.into_iter().map(Primary::Req).collect()))
+            .collect();
+        Expression(AndExpression(clauses))
+    }
+
+    fn dnf_terms(&self) -> Vec<BTreeSet<Requisite>> {
+        self.0 .0.iter().fold(vec![BTreeSet::new()], |terms, or| {
+            let options: Vec<BTreeSet<Requisite>> =
+                or.0.iter()
+                    .flat_map(|primary| match primary {
+                        Primary::Req(req) => vec![BTreeSet::from([req.clone()])],
+                        Primary::Expr(expression) => expression.dnf_terms(),
+                    })
+                    .collect();
+            let combined = terms
+                .iter()
+                .flat_map(|a| options.iter().map(move |b| a.union(b).cloned().collect()))
+                .collect();
+            minimize(combined)
+        })
+    }
+
+    fn cnf_clauses(&self) -> Vec<BTreeSet<Requisite>> {
+        let clauses = self
+            .0
+             .0
+            .iter()
+            .flat_map(|or| {
+                or.0.iter().fold(vec![BTreeSet::new()], |clauses, primary| {
+                    let options = match primary {
+                        Primary::Req(req) => vec![BTreeSet::from([req.clone()])],
+                        Primary::Expr(expression) => expression.cnf_clauses(),
+                    };
+                    let combined = clauses
+                        .iter()
+                        .flat_map(|a| options.iter().map(move |b| a.union(b).cloned().collect()))
+                        .collect();
+                    minimize(combined)
+                })
+            })
+            .collect();
+        minimize(clauses)
+    }
+}
+
+impl OrExpression {
+    /// See [`Expression::normalize`]. Returns `None` if one of the alternatives is an empty
+    /// expression, since the whole group is then always met.
+    fn normalize(&self) -> Option<OrExpression> {
+        let mut primaries: Vec<Primary> = Vec::new();
+        for primary in self.0.iter() {
+            match primary {
+                Primary::Req(req) => primaries.push(Primary::Req(req.clone())),
+                Primary::Expr(expression) => {
+                    let expression = expression.normalize();
+                    match expression.0 .0.len() {
+                        0 => return None,
+                        1 => primaries.extend(expression.0 .0.into_iter().next().unwrap().0),
+                        _ => primaries.push(Primary::Expr(expression)),
+                    }
+                }
+            }
+        }
+
+        // an alternative is a list of clauses which all need to be met
+        let primaries = absorb(primaries, |primary: &Primary| match primary {
+            Primary::Req(req) => vec![OrExpression(vec![Primary::Req(req.clone())])],
+            Primary::Expr(expression) => expression.0 .0.clone(),
+        });
+        Some(OrExpression(primaries))
+    }
+}
+
+/// Removes duplicate items and items whose parts are a superset of another item's parts, keeping
+/// the first of any duplicates.
+fn absorb<T: PartialEq, P: PartialEq>(items: Vec<T>, parts: impl Fn(&T) -> Vec<P>) -> Vec<T> {
+    let all_parts: Vec<Vec<P>> = items.iter().map(&parts).collect();
+    let subset = |a: &Vec<P>, b: &Vec<P>| a.iter().all(|part| b.contains(part));
+    items
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            !all_parts.iter().enumerate().any(|(j, other)| {
+                let this = &all_parts[*i];
+                // strictly smaller, or the same and earlier
+                subset(other, this) && (!subset(this, other) || j < *i)
+            })
+        })
+        .map(|(_, item)| item)
+        .collect()
+}
+
+impl Display for Primary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Primary::Req(requisite) => write!(f, "{requisite}"),
+            Primary::Expr(expression) => write!(f, "({expression})"),
+        }
+    }
+}
+
+impl Display for OrExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, primary) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " or ")?;
+            }
+            match primary {
+                // "A or concurrently or B" is ambiguous
+                Primary::Req(req) if req.typ != RequisiteType::Pre && self.0.len() > 1 => {
+                    write!(f, "({primary})")?
+                }
+                _ => write!(f, "{primary}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for AndExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, or) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " and ")?;
+            }
+            let corequisite = matches!(
+                or.0.as_slice(),
+                [Primary::Req(req)] if req.typ != RequisiteType::Pre
+            );
+            if self.0.len() > 1 && (or.0.len() > 1 || corequisite) {
+                write!(f, "({or})")?;
+            } else {
+                write!(f, "{or}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints the expression so that parsing it again gives an expression which normalizes to the
+/// same thing. Groups are parenthesized wherever precedence could be unclear.
+impl Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Requisites are written the way the registrar writes them:
+///
+/// ```text
+/// toplevel   := expression? EOF
+/// expression := operand (operator operand)*
+/// operator   := "and" | "or"
+/// operand    := requisite | "(" expression ")"
+/// requisite  := class_name ["or concurrently" | "concurrently"]
+/// class_name := DEPARTMENT NUMBER [MODIFIER]      (eg. PHY 183B)
+/// ```
+///
+/// Expressions are parsed by precedence climbing, with "and" binding tighter than "or". Older
+/// versions read `A and B or C` as `A and (B or C)`, so mixing "and" and "or" without
+/// parentheses is rejected rather than silently read either way: it has to be written as
+/// `(A and B) or C` or `A and (B or C)`. Whitespace is allowed anywhere between tokens, including
+/// just inside parentheses.
+///
+/// "or concurrently" (before or in the same term) and "concurrently" (in exactly the same term)
+/// belong to the class right before them, so they have to be in parentheses when that class is
+/// combined with anything else: `A and B or concurrently` could mean that either or both of the
+/// classes may be taken concurrently, and is rejected in favor of `A and (B or concurrently)`.
+/// They can't follow a parenthesized group either.
 mod parser {
     use nom::{
         branch::alt,
         bytes::complete::{tag, take_while1, take_while_m_n},
         character::complete::{multispace0, multispace1},
-        combinator::{all_consuming, cut, opt, recognize, success},
-        error::{context, convert_error, ContextError, ParseError, VerboseError},
-        multi::separated_list1,
+        combinator::{all_consuming, cut, fail, opt, peek, recognize, success},
+        error::{context, convert_error, ContextError, ErrorKind, ParseError, VerboseError},
         sequence::{delimited, pair, preceded, terminated, tuple},
         Finish, IResult, Parser,
     };
 
     use super::{AndExpression, Expression, OrExpression, Primary, Requisite, RequisiteType};
 
+    const AMBIGUOUS_CONCURRENTLY: &str =
+        "ambiguous \"concurrently\", put the class and \"concurrently\" in parentheses";
+    const AMBIGUOUS_AND_OR: &str =
+        "ambiguous mix of \"and\" and \"or\", put parentheses around one of them";
+
     fn is_uppercase(c: char) -> bool {
         c.is_ascii_uppercase()
     }
@@ -118,6 +537,85 @@ mod parser {
     trait Err<'a>: ParseError<&'a str> + ContextError<&'a str> {}
     impl<'a, T> Err<'a> for T where T: ParseError<&'a str> + ContextError<&'a str> {}
 
+    /// An expression as it was written, before being fit into [`Expression`]'s "and" of "or"s.
+    enum Node {
+        Req(Requisite),
+        /// An expression in parentheses.
+        Group(Box<Node>),
+        And(Vec<Node>),
+        Or(Vec<Node>),
+    }
+
+    impl Node {
+        fn into_expression(self) -> Expression {
+            match self {
+                Node::And(nodes) => Expression(AndExpression(
+                    nodes.into_iter().map(Node::into_or).collect(),
+                )),
+                node => Expression(AndExpression(vec![node.into_or()])),
+            }
+        }
+
+        fn into_or(self) -> OrExpression {
+            match self {
+                Node::Or(nodes) => {
+                    OrExpression(nodes.into_iter().map(Node::into_primary).collect())
+                }
+                node => OrExpression(vec![node.into_primary()]),
+            }
+        }
+
+        fn into_primary(self) -> Primary {
+            match self {
+                Node::Req(requisite) => Primary::Req(requisite),
+                Node::Group(node) => Primary::Expr(node.into_expression()),
+                node => Primary::Expr(node.into_expression()),
+            }
+        }
+
+        fn is_corequisite(&self) -> bool {
+            matches!(self, Node::Req(req) if req.typ != RequisiteType::Pre)
+        }
+
+        /// Whether joining this node with `op` would mix "and" and "or" without parentheses.
+        fn mixes_with(&self, op: Operator) -> bool {
+            matches!(
+                (op, self),
+                (Operator::And, Node::Or(_)) | (Operator::Or, Node::And(_))
+            )
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    enum Operator {
+        Or,
+        And,
+    }
+
+    impl Operator {
+        fn precedence(self) -> u8 {
+            match self {
+                Operator::Or => 1,
+                Operator::And => 2,
+            }
+        }
+
+        fn combine(self, lhs: Node, rhs: Node) -> Node {
+            match (self, lhs) {
+                (Operator::And, Node::And(mut nodes)) => {
+                    nodes.push(rhs);
+                    Node::And(nodes)
+                }
+                (Operator::Or, Node::Or(mut nodes)) => {
+                    nodes.push(rhs);
+                    Node::Or(nodes)
+                }
+                (Operator::And, lhs) => Node::And(vec![lhs, rhs]),
+                (Operator::Or, lhs) => Node::Or(vec![lhs, rhs]),
+            }
+        }
+    }
+
     fn class_name<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
         let department = take_while1(is_uppercase);
         let space = multispace1;
@@ -129,16 +627,19 @@ mod parser {
         context("class_name", name)(input)
     }
 
-    fn requesite_type<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, RequisiteType, E> {
-        let concurrent = pair(tag("or concurrent"), opt(tag("ly")));
-        let corequesite = concurrent.map(|_| RequisiteType::Co);
+    fn concurrently<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, RequisiteType, E> {
+        let word = || pair(tag("concurrent"), opt(tag("ly")));
+        let or_concurrent = tuple((tag("or"), multispace1, word())).map(|_| RequisiteType::Co);
+        let concurrent = word().map(|_| RequisiteType::Concurrent);
+        preceded(multispace0, alt((or_concurrent, concurrent))).parse(input)
+    }
 
-        let corequesite = context("corequesite", corequesite);
+    fn requesite_type<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, RequisiteType, E> {
+        let corequesite = context("corequesite", concurrently);
 
         let prerequesite = success(RequisiteType::Pre);
 
         let requesite_type = alt((corequesite, prerequesite));
-        let requesite_type = preceded(multispace0, requesite_type);
         context("requesite_type", requesite_type)(input)
     }
 
@@ -151,40 +652,72 @@ mod parser {
         context("requesite", requesite)(input)
     }
 
-    fn primary<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, Primary, E> {
-        let req = requisite.map(Primary::Req);
-        let and = parenthesis_helper(expression).map(Primary::Expr);
+    fn operand<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, Node, E> {
+        let req = requisite.map(Node::Req);
+        let group = parenthesis_helper(|i| climb(i, 0)).map(|node| Node::Group(Box::new(node)));
+
+        let operand = preceded(multispace0, alt((req, group)));
+        context("operand", operand)(input)
+    }
+
+    fn operator<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, Operator, E> {
+        let dangling = preceded(
+            peek(concurrently),
+            cut(context(
+                "\"concurrently\" must come right after a class name",
+                fail,
+            )),
+        );
+        let and = tag("and").map(|_| Operator::And);
+        let or = tag("or").map(|_| Operator::Or);
 
-        let expr = alt((req, and));
-        context("primary", expr)(input)
+        let separator = alt((multispace1, peek(tag("("))));
+        let operator = alt((dangling, delimited(multispace0, alt((and, or)), separator)));
+        context("operator", operator)(input)
     }
 
     fn parenthesis_helper<'a, T, E: Err<'a>>(
         parser: impl Parser<&'a str, T, E>,
     ) -> impl Parser<&'a str, T, E> {
-        let parenthesis = delimited(tag("("), parser, cut(tag(")")));
+        let open = pair(tag("("), multispace0);
+        let close = preceded(multispace0, tag(")"));
+        let parenthesis = preceded(open, cut(terminated(parser, close)));
         context("parenthesis", parenthesis)
     }
 
-    fn expression_helper<'a, T, E: Err<'a>>(
-        word: &'static str,
-        parser: impl Parser<&'a str, T, E>,
-    ) -> impl Parser<&'a str, Vec<T>, E> {
-        separated_list1(delimited(multispace0, tag(word), multispace0), parser)
-    }
-
-    fn or_expression<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, OrExpression, E> {
-        let expr = expression_helper("or", primary).map(OrExpression);
-        context("or_expression", expr)(input)
-    }
-
-    fn and_expression<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, AndExpression, E> {
-        let expr = expression_helper("and", or_expression).map(AndExpression);
-        context("and_expression", expr)(input)
+    /// Parses operands joined by operators which bind at least as tightly as `min_precedence`.
+    fn climb<'a, E: Err<'a>>(input: &'a str, min_precedence: u8) -> IResult<&'a str, Node, E> {
+        let start = input;
+        let (mut input, mut lhs) = operand(input)?;
+        loop {
+            let (rest, op) = match operator::<E>(input) {
+                Ok(res) => res,
+                Err(nom::Err::Error(_)) => break,
+                Err(err) => return Err(err),
+            };
+            if op.precedence() < min_precedence {
+                break;
+            }
+            let (after, rhs) = cut(|i| climb(i, op.precedence() + 1))(rest)?;
+            if lhs.is_corequisite() || rhs.is_corequisite() {
+                let at = if lhs.is_corequisite() { start } else { rest };
+                let err = E::from_error_kind(at, ErrorKind::Verify);
+                let err = E::add_context(at, AMBIGUOUS_CONCURRENTLY, err);
+                return Err(nom::Err::Failure(err));
+            }
+            if lhs.mixes_with(op) || rhs.mixes_with(op) {
+                let err = E::from_error_kind(start, ErrorKind::Verify);
+                let err = E::add_context(start, AMBIGUOUS_AND_OR, err);
+                return Err(nom::Err::Failure(err));
+            }
+            lhs = op.combine(lhs, rhs);
+            input = after;
+        }
+        Ok((input, lhs))
     }
 
     fn expression<'a, E: Err<'a>>(input: &'a str) -> IResult<&'a str, Expression, E> {
-        let expression = and_expression.map(Expression);
+        let expression = (|i| climb(i, 0)).map(Node::into_expression);
         context("expression", preceded(multispace0, expression))(input)
     }
 
@@ -250,8 +783,287 @@ mod parser {
         }
     }
 
+    #[test]
+    fn missing_requisites() {
+        use super::{ExplainExpression, RequesiteRegistry};
+        use std::collections::BTreeSet;
+
+        let taken = ["ECE 230".to_owned()];
+        let requisites_list = RequesiteRegistry {
+            prerequisites: &taken,
+            corequisites: &[],
+        };
+
+        let expression = verbose(
+            toplevel,
+            "(CSE 220 or CSE 232) and (ECE 230 and (ECE 203 or concurrently)) and (CSE 220 or MTH 101)",
+        )
+        .unwrap();
+        let evaluation = expression.explain(&requisites_list);
+        assert!(!evaluation.met());
+
+        let sets = |names: &[&[&str]]| -> Vec<BTreeSet<String>> {
+            names
+                .iter()
+                .map(|set| set.iter().map(|name| name.to_string()).collect())
+                .collect()
+        };
+        assert_eq!(
+            evaluation.missing(),
+            sets(&[&["CSE 220", "ECE 203"], &["CSE 232", "ECE 203", "MTH 101"]])
+        );
+
+        let expression = verbose(toplevel, "ECE 230 or FOO 100").unwrap();
+        assert!(expression.explain(&requisites_list).met());
+        assert!(expression.explain(&requisites_list).missing().is_empty());
+    }
+
+    #[test]
+    fn precedence() {
+        let parse = |input| verbose(toplevel, input).unwrap();
+
+        assert_eq!(
+            parse("ABC 100 and ABC 101 and ABC 102").0 .0.len(),
+            3,
+            "chains of the same operator are flattened"
+        );
+
+        assert_eq!(parse("( ABC 100 )"), parse("(ABC 100)"));
+        assert_eq!(
+            parse("  (  ABC 100   or\tABC 101 )and(ABC 102)  "),
+            parse("(ABC 100 or ABC 101) and (ABC 102)")
+        );
+        assert_eq!(
+            parse("( ABC 100 or  concurrently )"),
+            parse("(ABC 100 or concurrently)")
+        );
+
+        let concurrent = |input| parse(input).requisites()[0].typ;
+        assert_eq!(
+            concurrent("ABC 100 concurrently"),
+            RequisiteType::Concurrent
+        );
+        assert_eq!(
+            concurrent("(ABC 100 concurrent)"),
+            RequisiteType::Concurrent
+        );
+        assert_eq!(concurrent("ABC 100 or concurrently"), RequisiteType::Co);
+        assert_eq!(concurrent("ABC 100"), RequisiteType::Pre);
+    }
+
+    #[test]
+    fn mixed_operators() {
+        for input in [
+            "ABC 100 and ABC 101 or ABC 102",
+            "ABC 100 or ABC 101 and ABC 102",
+            "ABC 100 and ABC 101 or ABC 102 and ABC 103",
+            "(ABC 100 and ABC 101 or ABC 102) and ABC 103",
+        ] {
+            let err = verbose(toplevel, input).unwrap_err();
+            assert!(err.contains("ambiguous mix"), "{input}: {err}");
+        }
+        for input in [
+            "ABC 100 and ABC 101 and ABC 102",
+            "(ABC 100 and ABC 101) or ABC 102",
+            "ABC 100 and (ABC 101 or ABC 102)",
+            "ABC 100 and (ABC 101 or concurrently)",
+            "(ABC 100 and ABC 101) or (ABC 102 and ABC 103)",
+        ] {
+            assert!(verbose(toplevel, input).is_ok(), "{input}");
+        }
+    }
+
+    #[test]
+    fn ambiguous() {
+        let error = |input| verbose(toplevel, input).unwrap_err();
+
+        for input in [
+            "ABC 100 and ABC 101 or concurrently",
+            "ABC 100 or concurrently or ABC 101",
+            "ABC 100 or concurrently and ABC 101",
+            "ABC 100 and ABC 101 concurrently",
+            "ABC 100 concurrently or ABC 101",
+        ] {
+            assert!(error(input).contains("ambiguous"), "{input}");
+        }
+        for input in [
+            "(ABC 100 and ABC 101) or concurrently",
+            "ABC 100 or concurrently or concurrently",
+            "(ABC 100 or ABC 101) concurrently",
+        ] {
+            assert!(
+                error(input).contains("must come right after a class name"),
+                "{input}"
+            );
+        }
+        for input in [
+            "ABC 100 and",
+            "ABC 100 or or ABC 101",
+            "(ABC 100",
+            "()",
+            "and ABC 100",
+        ] {
+            error(input);
+        }
+    }
+
+    #[test]
+    fn registrar_strings() {
+        let cases = [
+            (
+                "((PHY 183 or concurrently) or (PHY 193H or concurrently) or PHY 183B) or (PHY 231 and (PHY 233B or concurrently)) or (PHY 231C and (PHY 233B or concurrently))",
+                "(PHY 183 or concurrently) or (PHY 193H or concurrently) or PHY 183B or (PHY 231 and (PHY 233B or concurrently)) or (PHY 231C and (PHY 233B or concurrently))",
+            ),
+            ("MTH 234 or MTH 254H or LB 220", "MTH 234 or MTH 254H or LB 220"),
+            (
+                "(ECE 201) and ((MTH 235 or concurrently) or MTH 340 or MTH 347H)",
+                "ECE 201 and ((MTH 235 or concurrently) or MTH 340 or MTH 347H)",
+            ),
+            ("ECE 202 or concurrently", "ECE 202 or concurrently"),
+            (
+                "(CSE 231 or concurrently) or (CSE 220 or concurrently)",
+                "(CSE 231 or concurrently) or (CSE 220 or concurrently)",
+            ),
+            (
+                "(MTH 234 or MTH 254H) and (ECE 201 or concurrently)",
+                "(MTH 234 or MTH 254H) and (ECE 201 or concurrently)",
+            ),
+            ("ECE 202", "ECE 202"),
+            (
+                "ECE 203 and (ECE 302 or concurrently) and (ECE 280 or concurrent)",
+                "ECE 203 and (ECE 302 or concurrently) and (ECE 280 or concurrently)",
+            ),
+            (
+                "(CSE 220 or CSE 232) and (ECE 230 and (ECE 203 or concurrently))",
+                "(CSE 220 or CSE 232) and ECE 230 and (ECE 203 or concurrently)",
+            ),
+        ];
+        for (input, normalized) in cases {
+            let expression = verbose(toplevel, input).unwrap();
+            assert_eq!(expression.normalize().to_string(), normalized);
+        }
+    }
+
+    #[cfg(test)]
+    fn xorshift(seed: &mut u64, n: u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed % n
+    }
+
+    /// Builds a random expression from a small pool of classes, so duplicates are common.
+    #[cfg(test)]
+    fn random_expression(seed: &mut u64, depth: u32) -> Expression {
+        let mut clauses = Vec::new();
+        for _ in 0..=xorshift(seed, 2) {
+            let mut primaries = Vec::new();
+            for _ in 0..=xorshift(seed, 2) {
+                if depth > 0 && xorshift(seed, 3) == 0 {
+                    primaries.push(Primary::Expr(random_expression(seed, depth - 1)));
+                    continue;
+                }
+                primaries.push(Primary::Req(Requisite {
+                    name: format!("ABC {}", 100 + xorshift(seed, 5)),
+                    typ: match xorshift(seed, 4) {
+                        0 => RequisiteType::Co,
+                        1 => RequisiteType::Concurrent,
+                        _ => RequisiteType::Pre,
+                    },
+                }));
+            }
+            clauses.push(OrExpression(primaries));
+        }
+        Expression(AndExpression(clauses))
+    }
+
+    #[test]
+    fn print_round_trip() {
+        use super::{EvalExpression, RequesiteRegistry};
+
+        let names: Vec<String> = (100..105).map(|n| format!("ABC {n}")).collect();
+        let mut seed = 0x2545f4914f6cdd1d;
+        for _ in 0..500 {
+            let expression = random_expression(&mut seed, 3);
+            let normalized = expression.normalize();
+            let printed = expression.to_string();
+
+            let reparsed = verbose(toplevel, &printed).unwrap();
+            assert_eq!(reparsed.normalize(), normalized, "{printed}");
+            assert_eq!(reparsed.to_string(), printed);
+            assert_eq!(normalized.normalize(), normalized, "{normalized}");
+            assert_eq!(
+                verbose(toplevel, &normalized.to_string())
+                    .unwrap()
+                    .normalize(),
+                normalized
+            );
+
+            let dnf = expression.to_dnf();
+            let cnf = expression.to_cnf();
+            assert_eq!(verbose(toplevel, &dnf.to_string()).unwrap().to_dnf(), dnf);
+            assert_eq!(verbose(toplevel, &cnf.to_string()).unwrap().to_cnf(), cnf);
+
+            // every combination of classes taken before and concurrently
+            for mask in 0..(1 << (names.len() * 2)) {
+                let prerequisites: Vec<String> = (0..names.len())
+                    .filter(|i| mask & (1 << i) != 0)
+                    .map(|i| names[i].clone())
+                    .collect();
+                let corequisites: Vec<String> = (0..names.len())
+                    .filter(|i| mask & (1 << (i + names.len())) != 0)
+                    .map(|i| names[i].clone())
+                    .collect();
+                let requisites_list = RequesiteRegistry {
+                    prerequisites: &prerequisites,
+                    corequisites: &corequisites,
+                };
+                let expected = expression.eval(&requisites_list);
+                assert_eq!(normalized.eval(&requisites_list), expected, "{normalized}");
+                assert_eq!(dnf.eval(&requisites_list), expected, "{dnf}");
+                assert_eq!(cnf.eval(&requisites_list), expected, "{cnf}");
+            }
+        }
+    }
+
+    #[test]
+    fn normalize() {
+        let normalize = |input| verbose(toplevel, input).unwrap().normalize().to_string();
+
+        assert_eq!(normalize("((CSE 220))"), "CSE 220");
+        assert_eq!(normalize("CSE 220 and (CSE 220 or CSE 232)"), "CSE 220");
+        assert_eq!(normalize("CSE 220 or (CSE 220 and CSE 232)"), "CSE 220");
+        assert_eq!(
+            normalize("(MTH 234 or MTH 254H) and (MTH 234 or MTH 254H) and ECE 201"),
+            "(MTH 234 or MTH 254H) and ECE 201"
+        );
+        assert_eq!(
+            normalize("((PHY 183 or concurrently) or PHY 183B) or (PHY 231 and PHY 233B)"),
+            "(PHY 183 or concurrently) or PHY 183B or (PHY 231 and PHY 233B)"
+        );
+        assert_eq!(
+            normalize("(CSE 220 or CSE 232) and (ECE 230 and (ECE 203 or concurrently))"),
+            "(CSE 220 or CSE 232) and ECE 230 and (ECE 203 or concurrently)"
+        );
+
+        let dnf = |input| verbose(toplevel, input).unwrap().to_dnf().to_string();
+        assert_eq!(
+            dnf("(CSE 220 or CSE 232) and ECE 230"),
+            "(CSE 220 and ECE 230) or (CSE 232 and ECE 230)"
+        );
+        let cnf = |input| verbose(toplevel, input).unwrap().to_cnf().to_string();
+        assert_eq!(
+            cnf("CSE 220 or (CSE 232 and ECE 230)"),
+            "(CSE 220 or CSE 232) and (CSE 220 or ECE 230)"
+        );
+    }
+
     #[allow(unused)]
     pub fn parse(input: &str) -> Expression {
         verbose(toplevel, input).unwrap()
     }
+
+    pub fn try_parse(input: &str) -> Result<Expression, String> {
+        verbose(toplevel, input)
+    }
 }
@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+use fastrand::Rng;
+
+use crate::config::Config;
+use crate::data::{Schedule, Term};
+use crate::limits::{LimitReached, Limits};
+
+/// Attempts made for each schedule asked for before giving up on finding more different ones.
+const ATTEMPTS_PER_SAMPLE: usize = 50;
+
+pub struct Samples {
+    pub schedules: Vec<Schedule>,
+    pub attempts: usize,
+    pub seed: u64,
+    /// The limit which stopped sampling, and the term being added when it did.
+    pub stopped: Option<(LimitReached, u8)>,
+}
+
+/// Picks up to `count` schedules by descending from `root` through random semesters, backtracking when a
+/// schedule can't be finished. Schedules too similar to one already picked are skipped. The
+/// search limits count every attempt together, so sampling stops once they're reached.
+pub fn sample(root: &Schedule, config: &Config, count: usize) -> Samples {
+    let seed = config.seed.unwrap_or_else(|| Rng::new().u64(..));
+    let mut rng = Rng::with_seed(seed);
+    let mut limits = Limits::new(config);
+
+    let mut schedules: Vec<Schedule> = Vec::new();
+    let mut picked: Vec<HashSet<(String, Term)>> = Vec::new();
+    let mut attempts = 0;
+    let mut stopped = None;
+    while schedules.len() < count && attempts < count * ATTEMPTS_PER_SAMPLE {
+        attempts += 1;
+        let sched = match descend(root.clone(), config, &mut rng, &mut limits, 0) {
+            Ok(Some(sched)) => sched,
+            // every path was tried without finishing a schedule
+            Ok(None) => break,
+            Err(limit) => {
+                stopped = Some(limit);
+                break;
+            }
+        };
+        let placements = sched.placements();
+        if picked
+            .iter()
+            .all(|other| distance(&placements, other) >= config.sample_distance)
+        {
+            schedules.push(sched);
+            picked.push(placements);
+        }
+    }
+
+    Samples {
+        schedules,
+        attempts,
+        seed,
+        stopped,
+    }
+}
+
+fn descend(
+    sched: Schedule,
+    config: &Config,
+    rng: &mut Rng,
+    limits: &mut Limits,
+    depth: u8,
+) -> Result<Option<Schedule>, (LimitReached, u8)> {
+    if depth == config.semesters || sched.finished(config) {
+        let complete = sched.is_complete(config).is_ok();
+        return Ok((complete || config.show_incomplete).then_some(sched));
+    }
+
+    // only the schedules on the current path are held, like when streaming
+    limits.check(0).map_err(|limit| (limit, depth + 1))?;
+    limits.nodes += 1;
+    let mut semesters: Vec<_> = sched
+        .generate_possible(config)
+        .into_iter()
+        .flatten()
+        .collect();
+    rng.shuffle(&mut semesters);
+    for child in semesters
+        .into_iter()
+        .filter_map(|sem| sched.child(sem).ok())
+    {
+        if let Some(found) = descend(child, config, rng, limits, depth + 1)? {
+            return Ok(Some(found));
+        }
+    }
+    Ok(None)
+}
+
+/// Jaccard distance: the share of placements in either schedule which aren't in both.
+fn distance(a: &HashSet<(String, Term)>, b: &HashSet<(String, Term)>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    1.0 - a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{classes, config, layout, root, taken};
+
+    #[test]
+    fn fixed_seed() {
+        let classes = classes(
+            "ABC,100,3,true,,Fall|Spring,
+             ABC,101,3,false,,Fall|Spring,ABC 100
+             ABC,102,3,false,,Fall|Spring,
+             ABC,103,3,false,,Spring,
+             ABC,104,3,false,,Fall,ABC 102",
+        );
+        let config = config(3, 6, 3, "seed = 42\nsample_distance = 0.1");
+        let root = root(&classes, &taken(&[]), &config);
+        let layouts = || -> Vec<Vec<Vec<String>>> {
+            let samples = sample(&root, &config, 5);
+            assert_eq!(samples.seed, 42);
+            samples.schedules.iter().map(layout).collect()
+        };
+        let first = layouts();
+        assert!(first.len() > 1);
+        assert_eq!(first, layouts());
+    }
+
+    #[test]
+    fn stop_at_limits() {
+        // the group needs more credits than there are, so no schedule is ever complete
+        let classes = classes(
+            "ABC,100,3,false,core,Fall|Spring,
+             ABC,101,3,false,core,Fall|Spring,
+             ABC,102,3,false,core,Fall|Spring,",
+        );
+        let samples = |extra: &str| {
+            let extra =
+                format!("seed = 1\nshow_incomplete = false\ngroups = {{ core = 12 }}\n{extra}");
+            let config = config(3, 6, 3, &extra);
+            sample(&root(&classes, &taken(&[]), &config), &config, 5)
+        };
+
+        let unlimited = samples("");
+        assert!(unlimited.schedules.is_empty());
+        assert_eq!(unlimited.stopped, None);
+
+        let limited = samples("max_nodes = 4");
+        assert!(limited.schedules.is_empty());
+        assert_eq!(limited.attempts, 1);
+        assert!(matches!(limited.stopped, Some((LimitReached::Nodes(4), _))));
+    }
+}
@@ -0,0 +1,865 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use itertools::Itertools;
+use microlp::{ComparisonOp, OptimizationDirection, Problem, Solution, Variable};
+use thiserror::Error;
+
+use crate::class::{self, Class};
+use crate::config::Config;
+use crate::data::{Schedule, ScheduleError, Semester, Term};
+use crate::degree::{Degree, Requirement};
+use crate::requirements::{Primary, RequisiteType};
+
+#[derive(Error, Debug)]
+pub enum SolveError {
+    #[error("No complete schedule fits within the constraints")]
+    Infeasible,
+    #[error("Solver failed: {0}")]
+    Solver(String),
+    #[error("Solver's schedule was rejected: {0}")]
+    Rejected(#[from] ScheduleError),
+}
+
+/// A sum of variables times coefficients. The solver doesn't allow a variable to appear twice in
+/// a constraint, so repeated variables are merged.
+#[derive(Debug, Clone, Default)]
+struct Sum(BTreeMap<Variable, f64>);
+
+impl Sum {
+    fn add(&mut self, var: Variable, coeff: f64) {
+        *self.0.entry(var).or_default() += coeff;
+    }
+
+    fn extend(&mut self, other: &Sum, coeff: f64) {
+        for (&var, &other_coeff) in other.0.iter() {
+            self.add(var, other_coeff * coeff);
+        }
+    }
+
+    fn terms(&self) -> Vec<(Variable, f64)> {
+        self.0.iter().map(|(&var, &coeff)| (var, coeff)).collect()
+    }
+}
+
+/// The schedule as an integer program: one variable for whether each class is taken in each term
+/// it's offered in, plus whatever else is needed to describe requisites and requirements.
+struct Model<'a> {
+    problem: Problem,
+    classes: &'a [Arc<Class>],
+    /// The schedule being continued, whose semesters are fixed.
+    root: &'a Schedule,
+    taken: &'a [String],
+    config: &'a Config,
+    terms: Vec<Term>,
+    /// Whether each class is taken in each term, if it's offered then.
+    took: Vec<Vec<Option<Variable>>>,
+    /// Credits each variable-credit class is taken for in each term.
+    credits: Vec<Vec<Option<Variable>>>,
+    /// Whether each term being planned is used. Schedules stop once they have `total_credits` or
+    /// have taken every class as many times as they can.
+    active: Vec<Option<Variable>>,
+    /// A variable fixed at 1, for constants.
+    one: Variable,
+}
+
+impl<'a> Model<'a> {
+    fn new(classes: &'a [Arc<Class>], root: &'a Schedule, config: &'a Config) -> Self {
+        let locked = root.semesters();
+        let terms: Vec<Term> = locked
+            .iter()
+            .map(|sem| sem.1)
+            .chain(
+                std::iter::successors(Some(root.next_term()), |term| Some(term.next()))
+                    .take(config.semesters.into()),
+            )
+            .collect();
+
+        // fewer terms always beats fewer credits when schedules stop once they have enough
+        let term_weight = match config.total_credits {
+            Some(_) => config.max_credits as f64 * config.semesters as f64 + 1.0,
+            None => 0.0,
+        };
+        let mut problem = Problem::new(OptimizationDirection::Minimize);
+        let one = problem.add_var(0.0, (1.0, 1.0));
+        let active: Vec<Option<Variable>> = terms
+            .iter()
+            .enumerate()
+            .map(|(index, _)| (index >= locked.len()).then(|| problem.add_binary_var(term_weight)))
+            .collect();
+
+        let mut took = Vec::new();
+        let mut credits = Vec::new();
+        for class in classes {
+            let (min, max) = class.credit_range();
+            let (class_took, class_credits): (Vec<_>, Vec<_>) = terms
+                .iter()
+                .map(|term| {
+                    if !class.offered(term) {
+                        return (None, None);
+                    }
+                    match class.is_variable() {
+                        true => (
+                            Some(problem.add_binary_var(0.0)),
+                            Some(problem.add_integer_var(1.0, (0, max.into()))),
+                        ),
+                        false => (Some(problem.add_binary_var(class.credits().into())), None),
+                    }
+                })
+                .unzip();
+
+            for (&took, &chosen) in class_took.iter().zip(class_credits.iter()) {
+                if let (Some(took), Some(chosen)) = (took, chosen) {
+                    problem.add_constraint(
+                        [(chosen, 1.0), (took, -(min as f64))],
+                        ComparisonOp::Ge,
+                        0.0,
+                    );
+                    problem.add_constraint(
+                        [(chosen, 1.0), (took, -(max as f64))],
+                        ComparisonOp::Le,
+                        0.0,
+                    );
+                }
+            }
+
+            // semesters being continued from are taken exactly as they were
+            for (index, semester) in locked.iter().enumerate() {
+                let planned = semester.0.iter().find(|other| *other == class);
+                if let Some(took) = class_took[index] {
+                    let times = planned.map_or(0.0, |_| 1.0);
+                    problem.add_constraint([(took, 1.0)], ComparisonOp::Eq, times);
+                }
+                if let (Some(chosen), Some(planned)) = (class_credits[index], planned) {
+                    problem.add_constraint(
+                        [(chosen, 1.0)],
+                        ComparisonOp::Eq,
+                        planned.credits().into(),
+                    );
+                }
+            }
+            took.push(class_took);
+            credits.push(class_credits);
+        }
+
+        Model {
+            problem,
+            classes,
+            root,
+            taken: root.taken(),
+            config,
+            terms,
+            took,
+            credits,
+            active,
+            one,
+        }
+    }
+
+    fn constrain(&mut self, sum: &Sum, op: ComparisonOp, rhs: f64) {
+        self.problem.add_constraint(sum.terms(), op, rhs);
+    }
+
+    /// Times the class is taken in the terms before `end`.
+    fn times_taken(&self, class: usize, end: usize) -> Sum {
+        let mut sum = Sum::default();
+        for var in self.took[class][..end].iter().flatten() {
+            sum.add(*var, 1.0);
+        }
+        sum
+    }
+
+    /// Credits the class is taken for in a term.
+    fn credits_in(&self, class: usize, term: usize) -> Sum {
+        let mut sum = Sum::default();
+        match (self.took[class][term], self.credits[class][term]) {
+            (_, Some(chosen)) => sum.add(chosen, 1.0),
+            (Some(took), None) => sum.add(took, self.classes[class].credits().into()),
+            (None, None) => {}
+        }
+        sum
+    }
+
+    /// Credits the class counts for in `term` when `counted` is 1, and nothing otherwise.
+    /// `counted` can only be 1 if the class is taken in `term`.
+    fn credits_counted(&mut self, class: usize, term: usize, counted: Variable) -> Sum {
+        let mut sum = Sum::default();
+        let Some(chosen) = self.credits[class][term] else {
+            sum.add(counted, self.classes[class].credits().into());
+            return sum;
+        };
+        // the credits chosen when counted, and 0 when not
+        let max = self.classes[class].credit_range().1 as f64;
+        let var = self.problem.add_integer_var(0.0, (0, max as i32));
+        self.problem
+            .add_constraint([(var, 1.0), (chosen, -1.0)], ComparisonOp::Le, 0.0);
+        self.problem
+            .add_constraint([(var, 1.0), (counted, -max)], ComparisonOp::Le, 0.0);
+        self.problem.add_constraint(
+            [(var, 1.0), (chosen, -1.0), (counted, -max)],
+            ComparisonOp::Ge,
+            -max,
+        );
+        sum.add(var, 1.0);
+        sum
+    }
+
+    fn total_credits(&self, class: usize) -> Sum {
+        let mut sum = Sum::default();
+        for term in 0..self.terms.len() {
+            sum.extend(&self.credits_in(class, term), 1.0);
+        }
+        sum
+    }
+
+    /// The taken class with this name, if it's in the catalog.
+    fn taken_class(&self, name: &str) -> Option<&'a Arc<Class>> {
+        self.root
+            .taken_classes()
+            .iter()
+            .find(|class| class.name() == name)
+    }
+
+    fn index(&self, name: &str) -> Vec<usize> {
+        (0..self.classes.len())
+            .filter(|&class| self.classes[class].name() == name)
+            .collect()
+    }
+
+    /// Credit limits for each term, and terms only being used once the ones before them are.
+    fn add_terms(&mut self) {
+        for term in 0..self.terms.len() {
+            let mut credits = Sum::default();
+            let mut classes = Sum::default();
+            for class in 0..self.classes.len() {
+                credits.extend(&self.credits_in(class, term), 1.0);
+                if let Some(took) = self.took[class][term] {
+                    classes.add(took, 1.0);
+                    if let Some(active) = self.active[term] {
+                        self.problem.add_constraint(
+                            [(took, 1.0), (active, -1.0)],
+                            ComparisonOp::Le,
+                            0.0,
+                        );
+                    }
+                }
+            }
+
+            let active = self.active[term].unwrap_or(self.one);
+            let (min, max) = (self.config.min_credits, self.config.max_credits);
+            let mut low = credits.clone();
+            low.add(active, -(min as f64));
+            self.constrain(&low, ComparisonOp::Ge, 0.0);
+            let mut high = credits;
+            high.add(active, -(max as f64));
+            self.constrain(&high, ComparisonOp::Le, 0.0);
+            classes.add(active, -1.0);
+            self.constrain(&classes, ComparisonOp::Ge, 0.0);
+
+            if term == 0 {
+                continue;
+            }
+            if let (Some(active), Some(before)) = (self.active[term], self.active[term - 1]) {
+                self.problem
+                    .add_constraint([(active, 1.0), (before, -1.0)], ComparisonOp::Le, 0.0);
+            }
+        }
+    }
+
+    /// Requisites, classes which must be taken concurrently, repeats and bundles.
+    fn add_classes(&mut self) {
+        for class in 0..self.classes.len() {
+            let repeats = self.times_taken(class, self.terms.len());
+            self.constrain(
+                &repeats,
+                ComparisonOp::Le,
+                self.classes[class].repeats().into(),
+            );
+
+            let clauses = self.classes[class]
+                .parsed_requisites()
+                .unwrap_or_default()
+                .to_cnf()
+                .0
+                 .0;
+            for clause in clauses {
+                let requisites: Vec<_> = clause
+                    .0
+                    .into_iter()
+                    .filter_map(|primary| match primary {
+                        Primary::Req(requisite) => Some(requisite),
+                        Primary::Expr(_) => None,
+                    })
+                    .collect();
+                if requisites.iter().any(|req| self.taken.contains(&req.name)) {
+                    continue;
+                }
+
+                for term in 0..self.terms.len() {
+                    let Some(took) = self.took[class][term] else {
+                        continue;
+                    };
+                    let mut met = Sum::default();
+                    for requisite in requisites.iter() {
+                        for other in self.index(&requisite.name) {
+                            let range = match requisite.typ {
+                                RequisiteType::Pre => 0..term,
+                                RequisiteType::Co => 0..term + 1,
+                                RequisiteType::Concurrent => term..term + 1,
+                            };
+                            for var in self.took[other][range].iter().flatten() {
+                                met.add(*var, 1.0);
+                            }
+                        }
+                    }
+                    let mut constraint = Sum::default();
+                    constraint.add(took, 1.0);
+                    constraint.extend(&met, -1.0);
+                    self.constrain(&constraint, ComparisonOp::Le, 0.0);
+                }
+            }
+
+            // taking a class's concurrent requisite means taking the class then, if not before
+            for linked in self.classes[class].linked().to_vec() {
+                for other in self.index(&linked) {
+                    for term in 0..self.terms.len() {
+                        let Some(took_other) = self.took[other][term] else {
+                            continue;
+                        };
+                        let mut constraint = self.times_taken(class, term + 1);
+                        constraint.add(took_other, -1.0);
+                        self.constrain(&constraint, ComparisonOp::Ge, 0.0);
+                    }
+                }
+            }
+        }
+
+        let bundles = class::units(self.classes)
+            .into_iter()
+            .filter(|unit| self.classes[unit[0]].bundle().is_some());
+        for members in bundles {
+            for term in 0..self.terms.len() {
+                let vars: Vec<Option<Variable>> = members
+                    .iter()
+                    .map(|&class| self.took[class][term])
+                    .collect();
+                if vars.iter().any(Option::is_none) {
+                    for var in vars.iter().flatten() {
+                        self.problem
+                            .add_constraint([(*var, 1.0)], ComparisonOp::Le, 0.0);
+                    }
+                    continue;
+                }
+                for pair in vars.windows(2) {
+                    if let [Some(first), Some(second)] = pair {
+                        self.problem.add_constraint(
+                            [(*first, 1.0), (*second, -1.0)],
+                            ComparisonOp::Eq,
+                            0.0,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Required classes and group minimums and maximums.
+    fn add_groups(&mut self) {
+        for class in 0..self.classes.len() {
+            if self.classes[class].required {
+                let taken = self.times_taken(class, self.terms.len());
+                self.constrain(&taken, ComparisonOp::Ge, 1.0);
+            }
+        }
+
+        // courses and credits counted towards each group, one term at a time so variable-credit
+        // classes count for the credits they're taken for
+        let config = self.config;
+        let mut counted: HashMap<&str, (Sum, Sum)> = HashMap::new();
+        for class in 0..self.classes.len() {
+            let groups: Vec<&str> = self.classes[class]
+                .groups()
+                .into_iter()
+                .filter(|group| config.groups.contains_key(*group))
+                .unique()
+                .collect();
+            for term in 0..self.terms.len() {
+                let Some(took) = self.took[class][term] else {
+                    continue;
+                };
+                // a class always counts towards its uncapped groups outside exclusive sets, and
+                // can be left out of capped groups to keep them under their maximum
+                let vars: Vec<Variable> = groups
+                    .iter()
+                    .map(|group| {
+                        let fixed = config.groups[*group].max_credits.is_none()
+                            && !config.exclusive.iter().flatten().any(|g| g == group);
+                        let (var, credits) = match fixed {
+                            true => (took, self.credits_in(class, term)),
+                            false => {
+                                let var = self.problem.add_binary_var(0.0);
+                                self.problem.add_constraint(
+                                    [(var, 1.0), (took, -1.0)],
+                                    ComparisonOp::Le,
+                                    0.0,
+                                );
+                                (var, self.credits_counted(class, term, var))
+                            }
+                        };
+                        let (courses, total) = counted.entry(group).or_default();
+                        courses.add(var, 1.0);
+                        total.extend(&credits, 1.0);
+                        var
+                    })
+                    .collect();
+
+                for set in config.exclusive.iter() {
+                    let mut constraint = Sum::default();
+                    constraint.add(took, 1.0);
+                    for (group, var) in groups.iter().zip(vars.iter()) {
+                        if set.iter().any(|name| name == group) {
+                            constraint.add(*var, -1.0);
+                        }
+                    }
+                    self.constrain(&constraint, ComparisonOp::Ge, 0.0);
+                }
+            }
+        }
+
+        for (name, group) in config.groups.iter() {
+            let (courses, credits) = counted.remove(name.as_str()).unwrap_or_default();
+            self.constrain(&credits, ComparisonOp::Ge, group.credits.into());
+            self.constrain(&courses, ComparisonOp::Ge, group.courses.into());
+            if let Some(max) = group.max_credits {
+                self.constrain(&credits, ComparisonOp::Le, max.into());
+            }
+        }
+    }
+
+    /// Every program in the degree being met. When courses can't double count, each time a
+    /// course is taken counts towards one of the programs which want it.
+    fn add_degree(&mut self, degree: &Degree) {
+        let programs = degree.programs.len();
+        let shared = !degree.double_count && programs > 1;
+
+        // times each class counts towards each program, and the credits it counts for
+        let mut occurrences = vec![vec![Sum::default(); self.classes.len()]; programs];
+        let mut credits = vec![vec![Sum::default(); self.classes.len()]; programs];
+        for class in 0..self.classes.len() {
+            let wanting = match shared {
+                true => {
+                    degree.mentioning(&self.classes[class].name(), &self.classes[class].groups())
+                }
+                false => (0..programs).collect(),
+            };
+            if wanting.len() < 2 || !shared {
+                let taken = self.times_taken(class, self.terms.len());
+                for program in wanting {
+                    occurrences[program][class] = taken.clone();
+                    credits[program][class] = self.total_credits(class);
+                }
+                continue;
+            }
+
+            // each time the class is taken counts towards at most one program, for the credits
+            // it's taken for then
+            for term in 0..self.terms.len() {
+                let Some(took) = self.took[class][term] else {
+                    continue;
+                };
+                let mut constraint = Sum::default();
+                constraint.add(took, 1.0);
+                for &program in wanting.iter() {
+                    let var = self.problem.add_binary_var(0.0);
+                    constraint.add(var, -1.0);
+                    occurrences[program][class].add(var, 1.0);
+                    let counted = self.credits_counted(class, term, var);
+                    credits[program][class].extend(&counted, 1.0);
+                }
+                self.constrain(&constraint, ComparisonOp::Ge, 0.0);
+            }
+        }
+
+        // whether each program counts each course in taken.txt
+        let mut taken = vec![BTreeMap::new(); programs];
+        for name in self.taken.iter() {
+            let groups = self
+                .taken_class(name)
+                .map_or(Vec::new(), |class| class.groups());
+            let wanting = match shared {
+                true => degree.mentioning(name, &groups),
+                false => (0..programs).collect(),
+            };
+            if wanting.len() < 2 || !shared {
+                for program in wanting {
+                    taken[program].insert(name.clone(), self.one);
+                }
+                continue;
+            }
+            let mut constraint = Sum::default();
+            for program in wanting {
+                let var = self.problem.add_binary_var(0.0);
+                constraint.add(var, 1.0);
+                taken[program].insert(name.clone(), var);
+            }
+            self.constrain(&constraint, ComparisonOp::Le, 1.0);
+        }
+
+        for (index, program) in degree.programs.iter().enumerate() {
+            let counts = Counts {
+                occurrences: &occurrences[index],
+                credits: &credits[index],
+                taken: &taken[index],
+            };
+            let met = self.add_requirement(program, &counts);
+            self.problem
+                .add_constraint([(met, 1.0)], ComparisonOp::Ge, 1.0);
+        }
+    }
+
+    /// A variable which can only be 1 if the block is met.
+    fn add_requirement(&mut self, requirement: &Requirement, counts: &Counts) -> Variable {
+        let met = self.problem.add_binary_var(0.0);
+
+        for course in requirement.courses.iter() {
+            let mut done = Sum::default();
+            for class in self.index(course) {
+                done.extend(&counts.occurrences[class], 1.0);
+            }
+            if let Some(var) = counts.taken.get(course) {
+                done.add(*var, 1.0);
+            }
+            done.add(met, -1.0);
+            self.constrain(&done, ComparisonOp::Ge, 0.0);
+        }
+
+        if requirement.count > 0 || requirement.credits > 0 {
+            let mut courses = Sum::default();
+            let mut credits = Sum::default();
+            for (class, data) in self.classes.iter().enumerate() {
+                let matches = requirement.from.contains(&data.name())
+                    || requirement
+                        .group
+                        .as_ref()
+                        .is_some_and(|group| data.groups().contains(&group.as_str()));
+                if matches {
+                    courses.extend(&counts.occurrences[class], 1.0);
+                    credits.extend(&counts.credits[class], 1.0);
+                }
+            }
+            // taken classes in the catalog count for their groups and credits too
+            for (name, &var) in counts.taken.iter() {
+                let class = self.taken_class(name);
+                let matches = requirement.from.contains(name)
+                    || requirement.group.as_ref().is_some_and(|group| {
+                        class.is_some_and(|class| class.groups().contains(&group.as_str()))
+                    });
+                if matches {
+                    courses.add(var, 1.0);
+                    if let Some(class) = class {
+                        credits.add(var, class.credits().into());
+                    }
+                }
+            }
+            courses.add(met, -(requirement.count as f64));
+            self.constrain(&courses, ComparisonOp::Ge, 0.0);
+            credits.add(met, -(requirement.credits as f64));
+            self.constrain(&credits, ComparisonOp::Ge, 0.0);
+        }
+
+        for child in requirement.all.iter() {
+            let child = self.add_requirement(child, counts);
+            self.problem
+                .add_constraint([(met, 1.0), (child, -1.0)], ComparisonOp::Le, 0.0);
+        }
+        if !requirement.any.is_empty() {
+            let mut constraint = Sum::default();
+            constraint.add(met, 1.0);
+            for child in requirement.any.iter() {
+                let child = self.add_requirement(child, counts);
+                constraint.add(child, -1.0);
+            }
+            self.constrain(&constraint, ComparisonOp::Le, 0.0);
+        }
+
+        met
+    }
+
+    /// Total credits, and terms only being left unused once the schedule has stopped: when the
+    /// terms before have enough credits or have taken every class as many times as it can be.
+    fn add_stopping(&mut self) {
+        let needed = self.config.total_credits.map(|target| {
+            target.saturating_sub(self.config.taken_credits + self.root.taken_credits())
+        });
+        let most = self.config.max_credits as f64 * self.terms.len() as f64;
+        let mut credits = Sum::default();
+        for term in 0..self.terms.len() {
+            if let Some(active) = self.active[term] {
+                let mut stopped = Sum::default();
+                stopped.add(active, 1.0);
+
+                let done = self.problem.add_binary_var(0.0);
+                for class in 0..self.classes.len() {
+                    let mut taken = self.times_taken(class, term);
+                    taken.add(done, -(self.classes[class].repeats() as f64));
+                    self.constrain(&taken, ComparisonOp::Ge, 0.0);
+                }
+                stopped.add(done, 1.0);
+
+                if let Some(needed) = needed {
+                    let reached = self.problem.add_binary_var(0.0);
+                    let mut enough = credits.clone();
+                    enough.add(reached, -(needed as f64));
+                    self.constrain(&enough, ComparisonOp::Ge, 0.0);
+                    stopped.add(reached, 1.0);
+
+                    // a term is only used if the terms before it don't have enough credits
+                    let mut before = credits.clone();
+                    before.add(active, most);
+                    self.constrain(&before, ComparisonOp::Le, most + needed as f64 - 1.0);
+                }
+                self.constrain(&stopped, ComparisonOp::Ge, 1.0);
+            }
+            for class in 0..self.classes.len() {
+                credits.extend(&self.credits_in(class, term), 1.0);
+            }
+        }
+        if let Some(needed) = needed {
+            self.constrain(&credits, ComparisonOp::Ge, needed.into());
+        }
+    }
+
+    /// Turns the solution back into a schedule, checking it the same way generated schedules are.
+    fn schedule(&self, solution: &Solution) -> Result<Schedule, ScheduleError> {
+        let mut sched = self.root.clone();
+        let locked = self.root.semesters().len();
+        for (index, term) in self.terms.iter().enumerate().skip(locked) {
+            if self.active[index].is_some_and(|active| solution[active] < 0.5) {
+                break;
+            }
+            let classes: Vec<Arc<Class>> = self
+                .classes
+                .iter()
+                .enumerate()
+                .filter(|(class, _)| {
+                    self.took[*class][index].is_some_and(|var| solution[var] > 0.5)
+                })
+                .map(|(class, data)| match self.credits[class][index] {
+                    Some(chosen) => {
+                        Arc::new(data.with_credits(solution.var_value_rounded(chosen) as u8))
+                    }
+                    None => data.clone(),
+                })
+                .collect();
+            sched = sched.child(Semester::new(classes, *term).verify(self.config)?)?;
+        }
+        sched.is_complete(self.config)?;
+        Ok(sched)
+    }
+}
+
+/// What counts towards one program.
+struct Counts<'a> {
+    occurrences: &'a [Sum],
+    credits: &'a [Sum],
+    taken: &'a BTreeMap<String, Variable>,
+}
+
+/// Finds a single complete schedule continuing from `root` with the fewest credits, or with the
+/// fewest terms and then the fewest credits when schedules stop once they reach `total_credits`.
+pub fn solve(
+    classes: &[Arc<Class>],
+    root: &Schedule,
+    config: &Config,
+) -> Result<Schedule, SolveError> {
+    // classes which were already taken are never scheduled again, like in the search
+    let classes: Vec<Arc<Class>> = classes
+        .iter()
+        .filter(|class| !class.used_up(root.taken()))
+        .cloned()
+        .collect();
+    let mut model = Model::new(&classes, root, config);
+    model.add_terms();
+    model.add_classes();
+    match &config.degree {
+        Some(degree) => model.add_degree(degree),
+        None => model.add_groups(),
+    }
+    model.add_stopping();
+
+    let solution = model.problem.solve().map_err(|err| match err {
+        microlp::Error::Infeasible => SolveError::Infeasible,
+        other => SolveError::Solver(other.to_string()),
+    })?;
+    Ok(model.schedule(&solution)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{classes, config, every_schedule, root, taken};
+
+    /// Checks that the solver finds a complete schedule exactly when enumerating every schedule
+    /// does, and that it's as short and has as few credits as the best one enumerated.
+    fn agree(
+        rows: &str,
+        (min, max, semesters): (u8, u8, u8),
+        extra: &str,
+        degree: Option<&str>,
+        taken_classes: &[&str],
+    ) {
+        let classes = classes(rows);
+        let mut config = config(min, max, semesters, extra);
+        config.degree = degree.map(|degree| toml::from_str(degree).unwrap());
+        let root = root(&classes, &taken(taken_classes), &config);
+        let key = |sched: &Schedule| match config.total_credits {
+            Some(_) => (sched.semesters().len(), sched.total_credits()),
+            None => (0, sched.total_credits()),
+        };
+        let best = every_schedule(root.clone(), &config)
+            .iter()
+            .filter(|sched| sched.is_complete(&config).is_ok())
+            .map(key)
+            .min();
+        match solve(&classes, &root, &config) {
+            Ok(sched) => assert_eq!(Some(key(&sched)), best, "{extra}"),
+            Err(SolveError::Infeasible) => assert_eq!(best, None, "{extra}"),
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    #[test]
+    fn caps_leave_out_only_capped_groups() {
+        let classes = classes(
+            "ABC,400,3,false,core|upper,Fall,
+             ABC,401,3,false,core|upper,Fall,",
+        );
+        let config = config(
+            3,
+            6,
+            1,
+            "groups = { core = 6, upper = { max_credits = 3 } }",
+        );
+        let root = root(&classes, &taken(&[]), &config);
+        let sched = solve(&classes, &root, &config).unwrap();
+        assert_eq!(sched.semesters()[0].0.len(), 2);
+    }
+
+    #[test]
+    fn stop_at_total_credits() {
+        let classes = classes(
+            "ABC,100,3,false,,Fall|Spring,
+             ABC,101,3,true,,Spring,
+             ABC,102,3,false,,Fall|Spring,",
+        );
+        // ABC 101 can't be taken until the second term, so 9 credits it is
+        let short = config(3, 6, 3, "total_credits = 3");
+        let root = root(&classes, &taken(&[]), &short);
+        assert!(matches!(
+            solve(&classes, &root, &short),
+            Err(SolveError::Infeasible)
+        ));
+
+        let long = config(3, 6, 3, "total_credits = 9");
+        let sched = solve(&classes, &root, &long).unwrap();
+        assert_eq!(sched.semesters().len(), 2);
+        assert_eq!(sched.total_credits(), 9);
+    }
+
+    #[test]
+    fn stop_once_everything_is_taken() {
+        let classes = classes(
+            "ABC,100,3,true,,Fall|Spring,
+             ABC,101,3,true,,Fall|Spring,",
+        );
+        let config = config(3, 6, 3, "");
+        let root = root(&classes, &taken(&[]), &config);
+        let sched = solve(&classes, &root, &config).unwrap();
+        assert!(sched.semesters().len() < 3);
+        assert_eq!(sched.total_credits(), 6);
+    }
+
+    #[test]
+    fn groups_agree_with_enumerating() {
+        let rows = "ABC,400,3,false,core|upper,Fall|Spring,
+                    ABC,401,3,false,core|upper,Fall|Spring,
+                    ABC,402,1-3,false,core|focus,Fall|Spring,
+                    ABC,403,2,false,upper|focus,Spring,
+                    ABC,404,2-4,false,upper,Fall,";
+        for groups in [
+            "groups = { core = 6, upper = { max_credits = 3 }, focus = 0 }",
+            "groups = { core = 7, upper = { max_credits = 3 }, focus = 2 }",
+            "groups = { core = 4, upper = { credits = 4, max_credits = 4 }, focus = 3 }",
+            "groups = { core = 6, upper = { credits = 3, max_credits = 3 }, focus = { courses = 2 } }",
+            "groups = { core = 6, upper = 5, focus = 3 }\nexclusive = [[\"upper\", \"focus\"]]",
+            "groups = { core = 3, upper = 4, focus = 3 }\nexclusive = [[\"core\", \"focus\"]]",
+            "groups = { core = 9, upper = 2, focus = 2 }\nexclusive = [[\"core\", \"focus\"]]",
+            "groups = { core = 6, upper = 6, focus = 0 }\ntotal_credits = 12",
+        ] {
+            agree(rows, (2, 7, 2), groups, None, &[]);
+        }
+    }
+
+    #[test]
+    fn degree_agrees_with_enumerating() {
+        let rows = "ABC,400,3,false,upper,Fall|Spring,
+                    ABC,401,1-3,false,upper,Fall|Spring,
+                    ABC,402,2,false,,Fall|Spring,
+                    ABC,403,2,false,,Spring,ABC 402";
+        for (double_count, major, minor) in [
+            (
+                false,
+                "group = \"upper\"\ncredits = 5",
+                "from = [\"ABC 401\", \"ABC 403\"]\ncount = 1",
+            ),
+            (
+                false,
+                "group = \"upper\"\ncredits = 6",
+                "from = [\"ABC 400\", \"ABC 401\"]\ncredits = 2",
+            ),
+            (
+                false,
+                "courses = [\"ABC 403\"]",
+                "from = [\"ABC 401\", \"ABC 403\"]\ncredits = 3",
+            ),
+            (
+                true,
+                "group = \"upper\"\ncredits = 6",
+                "from = [\"ABC 400\", \"ABC 401\"]\ncredits = 6",
+            ),
+        ] {
+            let degree = format!(
+                "double_count = {double_count}\n\
+                 [[programs]]\nname = \"Major\"\n{major}\n\
+                 [[programs]]\nname = \"Minor\"\n{minor}"
+            );
+            agree(rows, (2, 6, 2), "", Some(&degree), &[]);
+        }
+    }
+
+    #[test]
+    fn degree_with_taken_agrees_with_enumerating() {
+        let rows = "ABC,400,3,false,upper,Fall|Spring,
+                    ABC,401,1-3,false,upper,Fall|Spring,
+                    ABC,402,2,false,,Fall|Spring,
+                    ABC,403,2,false,,Spring,ABC 402";
+        // the taken upper level class counts for its group and credits, deep in the tree
+        let degree = "name = \"Degree\"
+                      [[all]]
+                      name = \"Upper\"
+                      group = \"upper\"
+                      credits = 5
+                      [[all]]
+                      name = \"Either\"
+                      [[all.any]]
+                      name = \"Lab\"
+                      courses = [\"ABC 403\"]
+                      [[all.any]]
+                      name = \"Pair\"
+                      group = \"upper\"
+                      count = 2";
+        for taken in [&[][..], &["ABC 400"], &["ABC 400", "ABC 402"]] {
+            agree(rows, (2, 6, 2), "", Some(degree), taken);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use yansi::Paint;
+
+use crate::config::Config;
+use crate::data::{ErrorCounts, Schedule, ScheduleState};
+use crate::limits::{LimitReached, Limits};
+
+/// A depth-first search which prints each schedule as soon as it has every semester, instead of
+/// holding every schedule until the end. Finds the same schedules as the usual search, but
+/// unsorted.
+pub struct Stream<'a, W: Write> {
+    config: &'a Config,
+    out: W,
+    pub errors: ErrorCounts,
+    seen: HashSet<ScheduleState>,
+    pub duplicates: usize,
+    pub printed: usize,
+    limits: Limits,
+    pub stopped: Option<(LimitReached, u8)>,
+}
+
+impl<'a, W: Write> Stream<'a, W> {
+    pub fn new(config: &'a Config, out: W) -> Self {
+        Stream {
+            config,
+            out,
+            errors: ErrorCounts::default(),
+            seen: HashSet::new(),
+            duplicates: 0,
+            printed: 0,
+            limits: Limits::new(config),
+            stopped: None,
+        }
+    }
+
+    /// Prints every schedule, failing if the output is closed (eg. piped into `head`).
+    pub fn run(&mut self, root: Schedule) -> io::Result<()> {
+        self.visit(root, 0)
+    }
+
+    fn visit(&mut self, sched: Schedule, term: u8) -> io::Result<()> {
+        if self.stopped.is_some() {
+            return Ok(());
+        }
+        let config = self.config;
+        if term < config.semesters && config.merge_duplicates && !self.seen.insert(sched.state()) {
+            self.duplicates += 1;
+            return Ok(());
+        }
+
+        if term == config.semesters || sched.finished(config) {
+            if config.show_incomplete || sched.is_complete(config).is_ok() {
+                writeln!(self.out, "{}", sched)?;
+                writeln!(
+                    self.out,
+                    "{} {}",
+                    "Complete:".yellow().bold(),
+                    sched.completeness_display(config)
+                )?;
+                writeln!(self.out)?;
+                self.printed += 1;
+            }
+            return Ok(());
+        }
+
+        // only the schedules on the current path are held, so there's nothing to cap
+        if let Err(limit) = self.limits.check(0) {
+            self.stopped = Some((limit, term + 1));
+            return Ok(());
+        }
+        self.limits.nodes += 1;
+        for result in sched.generate_possible(config) {
+            match result.and_then(|sem| sched.child(sem)) {
+                Ok(child) => self.visit(child, term + 1)?,
+                Err(err) => self.errors.add(&err),
+            }
+        }
+        // the schedules held are the ones on the path to this one
+        let held = term as usize + 1;
+        self.limits
+            .report(term + 1, config.semesters, held, self.errors.total);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{classes, config, root, taken};
+
+    #[test]
+    fn same_as_search() {
+        let classes = classes(
+            "ABC,100,3,true,,Fall|Spring,
+             ABC,101,3,false,,Fall|Spring,ABC 100
+             ABC,102,4,false,,Spring,
+             ABC,103,1-2,false,,Fall,ABC 101 or concurrently",
+        );
+        for extra in ["", "show_incomplete = false", "total_credits = 7"] {
+            let config = config(3, 6, 3, extra);
+            let root = root(&classes, &taken(&[]), &config);
+            let search = crate::search(root.clone(), &config);
+
+            let mut out = Vec::new();
+            let mut stream = Stream::new(&config, &mut out);
+            stream.run(root).unwrap();
+            let (printed, errors) = (stream.printed, stream.errors);
+            let out = String::from_utf8(out).unwrap();
+
+            assert!(printed > 0 && errors.total > 0, "{extra}");
+            assert_eq!(printed, search.schedules.len(), "{extra}");
+            assert_eq!(out.matches("Complete:").count(), printed, "{extra}");
+            assert_eq!(errors, search.errors, "{extra}");
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::class::Class;
+use crate::config::Config;
+use crate::data::Schedule;
+
+const HEADER: &str = "subject,number,credits,required,groups,semesters,requisites,bundle,repeats";
+
+/// Reads classes the same way as `input.csv`, with the header filled in. Rows can leave off the
+/// trailing `bundle` and `repeats` columns.
+pub fn classes(rows: &str) -> Vec<Arc<Class>> {
+    let rows: String = rows
+        .lines()
+        .map(str::trim)
+        .filter(|row| !row.is_empty())
+        .map(|row| {
+            let columns = row.split(',').count();
+            format!("{row}{}\n", ",".repeat(9 - columns))
+        })
+        .collect();
+    crate::read_classes(format!("{HEADER}\n{rows}").as_bytes())
+}
+
+/// A config starting in Fall 2024, with whatever else `extra` sets.
+pub fn config(min: u8, max: u8, semesters: u8, extra: &str) -> Config {
+    toml::from_str(&format!(
+        "min_credits = {min}\nmax_credits = {max}\nsemesters = {semesters}\n\
+         starting_term = {{ season = \"Fall\", year = 2024 }}\nprogress = false\n{extra}"
+    ))
+    .unwrap()
+}
+
+pub fn taken(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+pub fn root(classes: &[Arc<Class>], taken: &[String], config: &Config) -> Schedule {
+    Schedule::new(classes, Arc::new(taken.to_vec()), config.starting_term)
+}
+
+/// Every schedule with `config.semesters` semesters, or fewer if they finish early, found the
+/// simple way: adding every possible semester to every schedule, one term at a time.
+pub fn every_schedule(root: Schedule, config: &Config) -> Vec<Schedule> {
+    let mut scheds = vec![root];
+    let mut finished = Vec::new();
+    for _ in 0..config.semesters {
+        let (done, open): (Vec<Schedule>, Vec<Schedule>) =
+            scheds.into_iter().partition(|sched| sched.finished(config));
+        finished.extend(done);
+        scheds = open
+            .iter()
+            .flat_map(|sched| {
+                sched
+                    .generate_possible(config)
+                    .into_iter()
+                    .filter_map(|sem| sem.and_then(|sem| sched.child(sem)).ok())
+            })
+            .collect();
+    }
+    scheds.extend(finished);
+    scheds
+}
+
+/// Where each class is placed, for comparing schedules.
+pub fn layout(sched: &Schedule) -> Vec<Vec<String>> {
+    sched
+        .semesters()
+        .iter()
+        .map(|sem| sem.0.iter().map(|class| class.name()).sorted().collect())
+        .collect()
+}
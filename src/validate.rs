@@ -0,0 +1,321 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use enum_iterator::all;
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::class::{self, Class};
+use crate::config::Config;
+use crate::data::{Term, TermSeason};
+use crate::graph::RequisiteGraph;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("{0} is listed more than once")]
+    DuplicateClass(String),
+    #[error("Could not parse requisites for {0}:\n{1}")]
+    InvalidRequisites(String, String),
+    #[error("{0} requires {1}, which is not in the catalog or taken list")]
+    UnknownRequisite(String, String),
+    #[error("degree.toml names {0}, which is not in the catalog or taken list")]
+    UnknownDegreeCourse(String),
+    #[error("{0} can be repeated 0 times, leave repeats blank for classes taken once")]
+    NoRepeats(String),
+    #[error("{0} is in group {1}, which is not in config")]
+    UnknownGroup(String, String),
+    #[error("Exclusive groups include {0}, which is not in config")]
+    UnknownExclusiveGroup(String),
+    #[error("Group {0} needs {1} credits, but at most {2} count")]
+    ImpossibleGroup(String, u8, u8),
+    #[error("{0} is offered in \"{1}\", which never matches a term")]
+    UnknownSemester(String, String),
+    #[error("Bundle {0} is never offered in a single term: {}", .1.join(", "))]
+    BundleNeverOffered(String, Vec<String>),
+    #[error("{0} is required but can't be taken until {1}, after the first {2} semesters")]
+    TooLate(String, Term, u8),
+    #[error("At most {0} of the {1} total credits can be earned in {2} semesters")]
+    TotalCreditsUnreachable(u16, u16, u8),
+    #[error("{0} is required but can never be taken")]
+    Unreachable(String),
+    #[error("Prerequisite cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+pub fn validate(
+    classes: &[Arc<Class>],
+    taken: &[String],
+    config: &Config,
+    graph: &RequisiteGraph,
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    let mut seen = BTreeSet::new();
+    for class in classes {
+        if !seen.insert(class.name()) {
+            errors.push(ValidationError::DuplicateClass(class.name()));
+        }
+    }
+
+    // requisites which can't be parsed are reported with the graph's problems
+    for class in classes {
+        let Ok(expression) = class.parsed_requisites() else {
+            continue;
+        };
+        for requisite in expression.requisites() {
+            if !seen.contains(&requisite.name) && !taken.contains(&requisite.name) {
+                errors.push(ValidationError::UnknownRequisite(
+                    class.name(),
+                    requisite.name.clone(),
+                ));
+            }
+        }
+    }
+
+    for class in classes.iter().filter(|class| class.repeats() == 0) {
+        errors.push(ValidationError::NoRepeats(class.name()));
+    }
+
+    if let Some(degree) = &config.degree {
+        for course in degree.courses().into_iter().unique() {
+            if !seen.contains(course) && !taken.contains(course) {
+                errors.push(ValidationError::UnknownDegreeCourse(course.to_owned()));
+            }
+        }
+    }
+
+    for class in classes {
+        for group in class.groups() {
+            if !config.groups.contains_key(group) {
+                errors.push(ValidationError::UnknownGroup(
+                    class.name(),
+                    group.to_owned(),
+                ));
+            }
+        }
+    }
+
+    for group in config.exclusive.iter().flatten() {
+        if !config.groups.contains_key(group) {
+            errors.push(ValidationError::UnknownExclusiveGroup(group.to_owned()));
+        }
+    }
+
+    for (name, group) in config.groups.iter() {
+        if let Some(max) = group.max_credits.filter(|&max| max < group.credits) {
+            errors.push(ValidationError::ImpossibleGroup(
+                name.to_owned(),
+                group.credits,
+                max,
+            ));
+        }
+    }
+
+    // an even and an odd year for every season covers every way a term can match
+    let probes: Vec<Term> = all::<TermSeason>()
+        .flat_map(|season| [Term::new(season, 2000), Term::new(season, 2001)])
+        .collect();
+    for class in classes {
+        for semester in class.semesters() {
+            if !probes.iter().any(|term| term.matches(semester)) {
+                errors.push(ValidationError::UnknownSemester(
+                    class.name(),
+                    semester.to_owned(),
+                ));
+            }
+        }
+    }
+
+    for unit in class::units(classes) {
+        let members: Vec<&Arc<Class>> = unit.into_iter().map(|i| &classes[i]).collect();
+        let Some(bundle) = members[0].bundle() else {
+            continue;
+        };
+        if !probes
+            .iter()
+            .any(|term| members.iter().all(|class| class.offered(term)))
+        {
+            errors.push(ValidationError::BundleNeverOffered(
+                bundle.to_owned(),
+                members.iter().map(|class| class.name()).collect(),
+            ));
+        }
+    }
+
+    if let Some(target) = config.total_credits {
+        let catalog: u16 = classes
+            .iter()
+            .map(|class| class.credit_range().1 as u16 * class.repeats() as u16)
+            .sum();
+        let per_term = config.semesters as u16 * config.max_credits as u16;
+        let possible =
+            config.taken_credits + class::taken_credits(classes, taken) + catalog.min(per_term);
+        if possible < target {
+            errors.push(ValidationError::TotalCreditsUnreachable(
+                possible,
+                target,
+                config.semesters,
+            ));
+        }
+    }
+
+    errors.extend(reachability(graph, config));
+
+    errors
+}
+
+/// Requisites which can't be parsed, required classes which can't fit in the schedule and the
+/// cycles responsible, which would otherwise leave the planner with no complete schedules and no
+/// explanation.
+pub fn reachability(graph: &RequisiteGraph, config: &Config) -> Vec<ValidationError> {
+    let mut errors: Vec<ValidationError> = graph
+        .invalid()
+        .iter()
+        .map(|(name, err)| ValidationError::InvalidRequisites(name.clone(), err.clone()))
+        .collect();
+    errors.extend(
+        graph
+            .unsatisfiable(config.semesters)
+            .into_iter()
+            .map(|(class, earliest)| match earliest {
+                Some(term) => ValidationError::TooLate(class.name(), term, config.semesters),
+                None => ValidationError::Unreachable(class.name()),
+            }),
+    );
+    errors.extend(graph.cycles().into_iter().map(ValidationError::Cycle));
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::TermSeason;
+    use crate::testing::{classes, config, taken};
+
+    fn problems(rows: &str, taken: &[String], config: &Config) -> Vec<ValidationError> {
+        let classes = classes(rows);
+        let graph = RequisiteGraph::new(&classes, taken, config.starting_term);
+        validate(&classes, taken, config, &graph)
+    }
+
+    #[test]
+    fn catalog_problems() {
+        let config = config(
+            1,
+            10,
+            4,
+            "groups = { core = 3 }\nexclusive = [[\"core\", \"extra\"]]",
+        );
+        let errors = problems(
+            "ABC,100,3,true,core,Fall|Spring,
+             ABC,100,3,false,,Fall,,,0
+             ABC,101,3,false,labs,Fal,ABC 100
+             ABC,102,3,false,,Fall,ABC 100 and XYZ 999
+             ABC,103,3,false,,Fall,ABC 100 and and",
+            &taken(&[]),
+            &config,
+        );
+        assert_eq!(
+            errors[..6],
+            [
+                ValidationError::DuplicateClass("ABC 100".to_owned()),
+                ValidationError::UnknownRequisite("ABC 102".to_owned(), "XYZ 999".to_owned()),
+                ValidationError::NoRepeats("ABC 100".to_owned()),
+                ValidationError::UnknownGroup("ABC 101".to_owned(), "labs".to_owned()),
+                ValidationError::UnknownExclusiveGroup("extra".to_owned()),
+                ValidationError::UnknownSemester("ABC 101".to_owned(), "Fal".to_owned()),
+            ]
+        );
+        assert_eq!(errors.len(), 7);
+        assert!(
+            matches!(&errors[6], ValidationError::InvalidRequisites(name, _) if name == "ABC 103")
+        );
+    }
+
+    #[test]
+    fn mixed_and_or() {
+        let config = config(1, 10, 4, "");
+        let errors = problems(
+            "ABC,100,3,false,,Fall,
+             ABC,101,3,false,,Fall,
+             ABC,102,3,false,,Fall,ABC 100 and ABC 101 or ABC 999
+             ABC,103,3,false,,Fall,ABC 100 and (ABC 101 or ABC 102)",
+            &taken(&["ABC 999"]),
+            &config,
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(
+            matches!(&errors[0], ValidationError::InvalidRequisites(name, err)
+                if name == "ABC 102" && err.contains("ambiguous mix"))
+        );
+    }
+
+    #[test]
+    fn config_problems() {
+        let mut config = config(
+            1,
+            4,
+            2,
+            "total_credits = 20\ngroups = { core = { credits = 6, max_credits = 3 } }",
+        );
+        config.degree = Some(toml::from_str("name = \"Degree\"\ncourses = [\"XYZ 100\"]").unwrap());
+        let errors = problems(
+            "ABC,100,3,false,core,Fall,,lab
+             ABC,101,1,false,,Spring,,lab",
+            &taken(&[]),
+            &config,
+        );
+        assert_eq!(
+            errors,
+            [
+                ValidationError::UnknownDegreeCourse("XYZ 100".to_owned()),
+                ValidationError::ImpossibleGroup("core".to_owned(), 6, 3),
+                ValidationError::BundleNeverOffered(
+                    "lab".to_owned(),
+                    vec!["ABC 100".to_owned(), "ABC 101".to_owned()]
+                ),
+                ValidationError::TotalCreditsUnreachable(4, 20, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn reachability_problems() {
+        let config = config(1, 10, 2, "");
+        let errors = problems(
+            "ABC,100,3,true,,Spring,
+             ABC,200,3,true,,Fall,ABC 100
+             ABC,300,3,true,,Fall|Spring,ABC 301
+             ABC,301,3,false,,Fall|Spring,ABC 300
+             ABC,400,3,true,,Fall|Spring,ABC 401 or concurrently
+             ABC,401,3,false,,Fall|Spring,ABC 400 or concurrently",
+            &taken(&[]),
+            &config,
+        );
+        assert_eq!(
+            errors,
+            [
+                ValidationError::TooLate(
+                    "ABC 200".to_owned(),
+                    crate::data::Term::new(TermSeason::Fall, 2025),
+                    2
+                ),
+                ValidationError::Unreachable("ABC 300".to_owned()),
+                ValidationError::Cycle(vec![
+                    "ABC 300".to_owned(),
+                    "ABC 301".to_owned(),
+                    "ABC 300".to_owned()
+                ]),
+            ]
+        );
+
+        // taking either class breaks the cycle
+        let errors = problems(
+            "ABC,300,3,true,,Fall|Spring,ABC 301
+             ABC,301,3,false,,Fall|Spring,ABC 300",
+            &taken(&["ABC 301"]),
+            &config,
+        );
+        assert_eq!(errors, []);
+    }
+}